@@ -0,0 +1,154 @@
+use super::aabb::Aabb;
+use super::hitable::{HitRecord, Hitable};
+use super::ray::Ray;
+use super::texture::Texture;
+use std::sync::Arc;
+
+/// How many masked out surfaces a ray may pass through before giving up.
+const MAX_PASSES: usize = 32;
+
+/// Cuts holes into any `Hitable` with an opacity texture, for leaves,
+/// fences or decals. Hits where the mask, read as a single channel, is
+/// below `threshold` are ignored and the ray carries on. Without a
+/// threshold the mask is a coverage fraction and rays pass with
+/// probability 1 - alpha.
+///
+/// Every ray, whether from the camera or bounced towards a light, goes
+/// through `hit`, so the holes cast correct shadows too.
+pub struct AlphaMask {
+  obj: Arc<dyn Hitable>,
+  alpha: Arc<dyn Texture>,
+  threshold: Option<f32>,
+}
+
+impl AlphaMask {
+  /// A hard cutout at `threshold`.
+  pub fn new(
+    obj: Arc<dyn Hitable>,
+    alpha: Arc<dyn Texture>,
+    threshold: f32,
+  ) -> AlphaMask {
+    AlphaMask {
+      obj,
+      alpha,
+      threshold: Some(threshold),
+    }
+  }
+
+  /// Fractional coverage, resolved stochastically per hit.
+  pub fn stochastic(
+    obj: Arc<dyn Hitable>,
+    alpha: Arc<dyn Texture>,
+  ) -> AlphaMask {
+    AlphaMask {
+      obj,
+      alpha,
+      threshold: None,
+    }
+  }
+
+  fn is_opaque(&self, r: &Ray, hit: &HitRecord) -> bool {
+    let alpha = self.alpha.scalar_at(r, hit);
+    match self.threshold {
+      Some(threshold) => alpha >= threshold,
+      None => alpha >= 1_f32 || (alpha > 0_f32 && hash_to_unit(r, hit) < alpha),
+    }
+  }
+}
+
+/// Uniform number in [0, 1) from the ray and the hit point. Hashing
+/// rather than drawing a random number keeps the answer the same however
+/// many times the ray is traced, for instance through overlapping BVH
+/// nodes.
+fn hash_to_unit(r: &Ray, hit: &HitRecord) -> f32 {
+  let mut h = 0x9e37_79b9_7f4a_7c15_u64;
+  for &x in [
+    r.origin.x,
+    r.origin.y,
+    r.origin.z,
+    r.direction.x,
+    r.direction.y,
+    r.direction.z,
+    hit.t,
+  ]
+  .iter()
+  {
+    h ^= u64::from(x.to_bits());
+    h = h.wrapping_mul(0xbf58_476d_1ce4_e5b9_u64);
+    h ^= h >> 31;
+  }
+
+  (h >> 40) as f32 / (1_u64 << 24) as f32
+}
+
+impl Hitable for AlphaMask {
+  fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    let mut t_min = t_min;
+    for _ in 0..MAX_PASSES {
+      let hit = self.obj.hit(r, t_min, t_max)?;
+      if self.is_opaque(r, &hit) {
+        return Some(hit);
+      }
+      //
+      // Shapes accept hits at exactly t_min, so step just past this one
+      // or the same surface comes straight back.
+      t_min = hit.t.next_up();
+    }
+
+    None
+  }
+
+  fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+    self.obj.bounding_box(t0, t1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+  use crate::hitable_list::HitableList;
+  use crate::lambertian::Lambertian;
+  use crate::texture::TexturePoint;
+  use crate::vec3::Vec3;
+  use crate::xy_rect::XYRect;
+
+  /// Transparent in front of z = 0.5, opaque behind it.
+  struct FrontCutout;
+
+  impl Texture for FrontCutout {
+    fn lookup(&self, at: &TexturePoint) -> Vec3 {
+      Vec3::same(if at.p.z > 0.5_f32 { 0_f32 } else { 1_f32 })
+    }
+  }
+
+  #[test]
+  fn test_masked_rect_in_front_of_opaque_rect() {
+    let mtl = Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+      Vec3::same(0.5_f32),
+    ))));
+    let mut rects = HitableList::new();
+    rects.add_object(Arc::new(XYRect::new(
+      -1_f32,
+      1_f32,
+      -1_f32,
+      1_f32,
+      1_f32,
+      mtl.clone(),
+    )));
+    rects.add_object(Arc::new(XYRect::new(
+      -1_f32, 1_f32, -1_f32, 1_f32, 0_f32, mtl,
+    )));
+    let rects: Arc<dyn Hitable> = Arc::new(rects);
+
+    let r = Ray::new(
+      Vec3::new(0_f32, 0_f32, 5_f32),
+      Vec3::new(0_f32, 0_f32, -1_f32),
+      0_f32,
+    );
+    let cutout = AlphaMask::new(rects.clone(), Arc::new(FrontCutout), 0.5_f32);
+    assert_eq!(cutout.hit(&r, 0.001_f32, f32::MAX).unwrap().t, 5_f32);
+    let coverage = AlphaMask::stochastic(rects, Arc::new(FrontCutout));
+    assert_eq!(coverage.hit(&r, 0.001_f32, f32::MAX).unwrap().t, 5_f32);
+  }
+}
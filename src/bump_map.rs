@@ -0,0 +1,143 @@
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::{dot_product, unit_vector, Vec3};
+use std::sync::Arc;
+
+/// Distance the surface is pushed from the ray origin, along the geometric
+/// normal, so perturbed rays don't hit the surface they start on.
+const RAY_OFFSET: f32 = 1e-4_f32;
+
+/// Scatters `base` as if the surface at `h` had the shading normal `n`.
+///
+/// The shading normal only changes how the surface is lit; whether light
+/// goes in or out is up to the geometric normal. Samples on which the two
+/// disagree would leak light through the surface and are dropped.
+pub fn scatter_with_shading_normal(
+  base: &dyn Material,
+  r: &Ray,
+  h: &HitRecord,
+  n: Vec3,
+) -> Option<(Vec3, Ray)> {
+  let ng = h.geometric_normal;
+  if n.squared_length() < 1e-12_f32 {
+    return base.scatter(r, h);
+  }
+  let n = unit_vector(n);
+
+  //
+  // A normal tilted past the horizon would turn the surface inside out.
+  let n = if dot_product(n, ng) < 0.01_f32 {
+    unit_vector(n + (0.01_f32 - dot_product(n, ng)) * ng)
+  } else {
+    n
+  };
+
+  let same_side = |d: Vec3, a: Vec3, b: Vec3| {
+    (dot_product(d, a) > 0_f32) == (dot_product(d, b) > 0_f32)
+  };
+  if !same_side(r.direction, n, ng) {
+    return base.scatter(r, h);
+  }
+
+  let mut shaded = h.clone();
+  shaded.normal = n;
+  let (attenuation, mut scattered) = base.scatter(r, &shaded)?;
+  if !same_side(scattered.direction, n, ng) {
+    return None;
+  }
+
+  //
+  // Materials that move the ray elsewhere, such as subsurface walks, have
+  // already placed it.
+  if scattered.origin == h.p {
+    let side = if dot_product(scattered.direction, ng) > 0_f32 {
+      1_f32
+    } else {
+      -1_f32
+    };
+    scattered.origin = h.p + (side * RAY_OFFSET) * ng;
+  }
+
+  Some((attenuation, scattered))
+}
+
+/// Bump mapping: wraps a material and tilts its shading normal with the
+/// slope of a height field given by a scalar texture.
+///
+/// The slope is found by stepping the lookup a short distance along the
+/// tangent and bitangent, moving the position and (u, v) by the same
+/// amount of surface, so solid and image heights both work; detailed
+/// image heights are still better baked into a `NormalMap`.
+pub struct BumpMap {
+  pub base: Arc<dyn Material>,
+  pub height: Arc<dyn Texture>,
+  /// Height in world units of a texture value of 1.
+  pub strength: f32,
+}
+
+/// Finite difference step of the slope, in world units.
+const BUMP_STEP: f32 = 1e-3_f32;
+
+impl BumpMap {
+  pub fn new(
+    base: Arc<dyn Material>,
+    height: Arc<dyn Texture>,
+    strength: f32,
+  ) -> BumpMap {
+    BumpMap {
+      base,
+      height,
+      strength,
+    }
+  }
+
+  fn shading_normal(&self, r: &Ray, h: &HitRecord) -> Vec3 {
+    let frame = Onb::from_w_u(h.normal, h.tangent);
+
+    //
+    // The change in (u, v) that covers BUMP_STEP of surface, or the step
+    // itself where the surface doesn't say how (u, v) stretch.
+    let uv_step = |dpd: Vec3| {
+      let len = dpd.length();
+      if len > 1e-8_f32 {
+        BUMP_STEP / len
+      } else {
+        BUMP_STEP
+      }
+    };
+    let (du, dv) = (uv_step(h.dpdu), uv_step(h.dpdv));
+    let at = TexturePoint::at(r, h);
+    let height = |s: f32, t: f32| {
+      self.height.scalar(&TexturePoint {
+        u: h.u + s * du,
+        v: h.v + t * dv,
+        p: h.p + (s * BUMP_STEP) * frame.u + (t * BUMP_STEP) * frame.v,
+        ..at
+      })
+    };
+
+    let h0 = height(0_f32, 0_f32);
+    let slope_u = (height(1_f32, 0_f32) - h0) / BUMP_STEP;
+    let slope_v = (height(0_f32, 1_f32) - h0) / BUMP_STEP;
+
+    frame.w - self.strength * (slope_u * frame.u + slope_v * frame.v)
+  }
+}
+
+impl Material for BumpMap {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    scatter_with_shading_normal(
+      self.base.as_ref(),
+      r,
+      h,
+      self.shading_normal(r, h),
+    )
+  }
+
+  fn emitted(&self, r: &Ray, h: &HitRecord) -> Vec3 {
+    self.base.emitted(r, h)
+  }
+}
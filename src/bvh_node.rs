@@ -7,8 +7,8 @@ use std::sync::Arc;
 
 pub struct BvhNode {
   pub bbox: Aabb,
-  pub left: Arc<Hitable>,
-  pub right: Arc<Hitable>,
+  pub left: Arc<dyn Hitable>,
+  pub right: Arc<dyn Hitable>,
 }
 
 impl Hitable for BvhNode {
@@ -17,22 +17,18 @@ impl Hitable for BvhNode {
       let hit_left_rec = self.left.hit(r, t_min, t_max);
       let hit_right_rec = self.right.hit(r, t_min, t_max);
 
-      if hit_left_rec.is_some() && hit_right_rec.is_some() {
-        let lrec = hit_left_rec.unwrap();
-        let rrec = hit_right_rec.unwrap();
-
-        if lrec.t < rrec.t {
-          return Some(lrec);
-        } else {
-          return Some(rrec);
+      return match (hit_left_rec, hit_right_rec) {
+        (Some(lrec), Some(rrec)) => {
+          if lrec.t < rrec.t {
+            Some(lrec)
+          } else {
+            Some(rrec)
+          }
         }
-      } else if hit_left_rec.is_some() {
-        return hit_left_rec;
-      } else if hit_right_rec.is_some() {
-        return hit_right_rec;
-      } else {
-        return None;
-      }
+        (Some(lrec), None) => Some(lrec),
+        (None, Some(rrec)) => Some(rrec),
+        (None, None) => None,
+      };
     }
 
     None
@@ -44,7 +40,7 @@ impl Hitable for BvhNode {
 }
 
 impl BvhNode {
-  pub fn new(l: &mut [Arc<Hitable>], time0: f32, time1: f32) -> Arc<BvhNode> {
+  pub fn new(l: &mut [Arc<dyn Hitable>], time0: f32, time1: f32) -> Arc<BvhNode> {
     let mut rng = thread_rng();
 
     let axis = (3_f32 * rng.gen::<f32>()) as i32;
@@ -91,20 +87,18 @@ impl BvhNode {
       1 => (l[0].clone(), l[0].clone()),
       2 => (l[0].clone(), l[1].clone()),
       _ => (
-        BvhNode::new(&mut l[0..n / 2], time0, time1) as Arc<Hitable>,
-        BvhNode::new(&mut l[n / 2..], time0, time1) as Arc<Hitable>,
+        BvhNode::new(&mut l[0..n / 2], time0, time1) as Arc<dyn Hitable>,
+        BvhNode::new(&mut l[n / 2..], time0, time1) as Arc<dyn Hitable>,
       ),
     };
 
     let box_left = left.bounding_box(time0, time1);
     let box_right = right.bounding_box(time0, time1);
 
-    let bbox = if box_left.is_some() && box_right.is_some() {
-      Aabb::merge(&box_left.unwrap(), &box_right.unwrap())
-    } else if box_left.is_some() {
-      box_left.unwrap()
-    } else {
-      box_right.unwrap()
+    let bbox = match (box_left, box_right) {
+      (Some(bl), Some(br)) => Aabb::merge(&bl, &br),
+      (Some(bl), None) => bl,
+      (None, br) => br.unwrap(),
     };
 
     Arc::new(BvhNode { bbox, left, right })
@@ -1,4 +1,5 @@
 use super::ray::Ray;
+use super::ray_differential::RayDifferential;
 use super::vec3::{cross_product, random_in_unit_disk, unit_vector, Vec3};
 use rand::prelude::*;
 
@@ -35,6 +36,7 @@ impl Default for CameraParameters {
 }
 
 impl Camera {
+  #[allow(clippy::too_many_arguments)]
   pub fn new(
     lookfrom: Vec3,
     lookat: Vec3,
@@ -72,18 +74,26 @@ impl Camera {
     }
   }
 
-  pub fn ray_at(&self, s: f32, t: f32) -> Ray {
+  /// Ray through (s, t) on the image, in [0, 1] from the bottom left.
+  /// Its differentials go through (s + ds, t) and (s, t + dt), through the
+  /// same point on the lens.
+  pub fn ray_at(&self, s: f32, t: f32, ds: f32, dt: f32) -> Ray {
     let rd = self.lens_radius * random_in_unit_disk();
-    let offset = self.u * rd.x + self.v * rd.y;
+    let origin = self.origin + self.u * rd.x + self.v * rd.y;
     let time =
       self.time0 + thread_rng().gen::<f32>() * (self.time1 - self.time0);
-
-    Ray::new(
-      self.origin + offset,
+    let direction = |s: f32, t: f32| {
       self.lower_left_corner + s * self.horizontal + t * self.vertical
-        - self.origin
-        - offset,
-      time,
-    )
+        - origin
+    };
+
+    Ray::new(origin, direction(s, t), time).with_differential(Some(
+      RayDifferential {
+        rx_origin: origin,
+        rx_direction: direction(s + ds, t),
+        ry_origin: origin,
+        ry_direction: direction(s, t + dt),
+      },
+    ))
   }
 }
@@ -1,25 +1,29 @@
 use super::vec3::Vec3;
-use super::texture::Texture;
+use super::texture::{Texture, TexturePoint};
 use std::sync::Arc;
 
 pub struct CheckerTexture {
-  odd : Arc<Texture>,
-  even : Arc<Texture>
+  odd : Arc<dyn Texture>,
+  even : Arc<dyn Texture>
 }
 
 impl CheckerTexture {
-  pub fn new(odd : Arc<Texture>, even : Arc<Texture>) -> CheckerTexture {
+  pub fn new(odd : Arc<dyn Texture>, even : Arc<dyn Texture>) -> CheckerTexture {
     CheckerTexture{odd, even}
   }
-}
 
-impl Texture for CheckerTexture {
-  fn value(&self, u : f32, v : f32, p : Vec3) -> Vec3 {
+  fn pick(&self, p : Vec3) -> &dyn Texture {
     let sines = (10_f32 * p.x).sin() * (10_f32 * p.y).sin() * (10_f32 * p.z).sin();
     if sines < 0_f32 {
-      self.odd.value(u, v, p)
+      self.odd.as_ref()
     } else {
-      self.even.value(u, v, p)
+      self.even.as_ref()
     }
   }
+}
+
+impl Texture for CheckerTexture {
+  fn lookup(&self, at : &TexturePoint) -> Vec3 {
+    self.pick(at.p).lookup(at)
+  }
 }
\ No newline at end of file
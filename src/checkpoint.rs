@@ -0,0 +1,274 @@
+use super::film::{Film, FilmPixel};
+use super::filter::FilterKind;
+use super::sampler::Sampler;
+use super::vec3::Vec3;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"RTCK";
+const VERSION: u32 = 2;
+
+/// Bytes stored per film pixel: the contribution, the filter weight and
+/// the sample count.
+const PIXEL_BYTES: u64 = 20;
+
+/// Everything besides the film that is needed to continue a render.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CheckpointHeader {
+  pub scene: String,
+  /// Seed the scene was built from, so a resumed render sees the same
+  /// randomly generated world.
+  pub scene_seed: u64,
+  pub filter: FilterKind,
+  pub filter_radius: f32,
+  /// Samples per pixel of all the passes completed so far.
+  pub spp: u32,
+  pub sampler: Sampler,
+}
+
+fn filter_id(kind: FilterKind) -> u32 {
+  match kind {
+    FilterKind::Box => 0,
+    FilterKind::Tent => 1,
+    FilterKind::Gaussian => 2,
+    FilterKind::Mitchell => 3,
+    FilterKind::Lanczos => 4,
+  }
+}
+
+fn filter_from_id(id: u32) -> io::Result<FilterKind> {
+  match id {
+    0 => Ok(FilterKind::Box),
+    1 => Ok(FilterKind::Tent),
+    2 => Ok(FilterKind::Gaussian),
+    3 => Ok(FilterKind::Mitchell),
+    4 => Ok(FilterKind::Lanczos),
+    _ => Err(invalid_data("unknown filter in checkpoint")),
+  }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_u32<W: Write>(w: &mut W, v: u32) -> io::Result<()> {
+  w.write_all(&v.to_le_bytes())
+}
+
+fn write_u64<W: Write>(w: &mut W, v: u64) -> io::Result<()> {
+  w.write_all(&v.to_le_bytes())
+}
+
+fn write_f32<W: Write>(w: &mut W, v: f32) -> io::Result<()> {
+  w.write_all(&v.to_bits().to_le_bytes())
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+  let mut buf = [0_u8; 4];
+  r.read_exact(&mut buf)?;
+  Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+  let mut buf = [0_u8; 8];
+  r.read_exact(&mut buf)?;
+  Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f32<R: Read>(r: &mut R) -> io::Result<f32> {
+  Ok(f32::from_bits(read_u32(r)?))
+}
+
+/// Bytes left between the read position of `r` and `len`.
+fn remaining<R: Seek>(r: &mut R, len: u64) -> io::Result<u64> {
+  Ok(len.saturating_sub(r.stream_position()?))
+}
+
+/// Writes the film and render state to `path`. The data goes to a
+/// temporary file first, so an interrupted write never clobbers the
+/// previous checkpoint.
+pub fn save<P: AsRef<Path>>(
+  path: P,
+  header: &CheckpointHeader,
+  film: &Film,
+) -> io::Result<()> {
+  let path = path.as_ref();
+  let tmp_path = path.with_extension("tmp");
+
+  {
+    let mut w = BufWriter::new(File::create(&tmp_path)?);
+
+    w.write_all(MAGIC)?;
+    write_u32(&mut w, VERSION)?;
+    write_u32(&mut w, film.width)?;
+    write_u32(&mut w, film.height)?;
+    write_u32(&mut w, filter_id(header.filter))?;
+    write_f32(&mut w, header.filter_radius)?;
+    write_u32(&mut w, header.spp)?;
+    write_u64(&mut w, header.sampler.seed)?;
+    write_u64(&mut w, header.sampler.pass)?;
+    write_u32(&mut w, header.scene.len() as u32)?;
+    w.write_all(header.scene.as_bytes())?;
+    write_u64(&mut w, header.scene_seed)?;
+
+    for pixel in film.pixels() {
+      write_f32(&mut w, pixel.contrib_sum.x)?;
+      write_f32(&mut w, pixel.contrib_sum.y)?;
+      write_f32(&mut w, pixel.contrib_sum.z)?;
+      write_f32(&mut w, pixel.filter_weight_sum)?;
+      write_u32(&mut w, pixel.sample_count)?;
+    }
+
+    w.flush()?;
+  }
+
+  fs::rename(&tmp_path, path)
+}
+
+pub fn load<P: AsRef<Path>>(path: P) -> io::Result<(CheckpointHeader, Film)> {
+  let file = File::open(path)?;
+  let len = file.metadata()?.len();
+  let mut r = BufReader::new(file);
+
+  let mut magic = [0_u8; 4];
+  r.read_exact(&mut magic)?;
+  if &magic != MAGIC {
+    return Err(invalid_data("not a checkpoint file"));
+  }
+
+  if read_u32(&mut r)? != VERSION {
+    return Err(invalid_data("unsupported checkpoint version"));
+  }
+
+  let width = read_u32(&mut r)?;
+  let height = read_u32(&mut r)?;
+  let filter = filter_from_id(read_u32(&mut r)?)?;
+  let filter_radius = read_f32(&mut r)?;
+  let spp = read_u32(&mut r)?;
+  let sampler = Sampler {
+    seed: read_u64(&mut r)?,
+    pass: read_u64(&mut r)?,
+  };
+
+  let scene_len = read_u32(&mut r)? as u64;
+  if scene_len > remaining(&mut r, len)? {
+    return Err(invalid_data("checkpoint is truncated"));
+  }
+  let mut scene = vec![0_u8; scene_len as usize];
+  r.read_exact(&mut scene)?;
+  let scene =
+    String::from_utf8(scene).map_err(|_| invalid_data("bad scene name"))?;
+  let scene_seed = read_u64(&mut r)?;
+
+  //
+  // The size in the header decides how much to allocate, so check it
+  // against the file before trusting it.
+  let pixel_count = width as u64 * height as u64;
+  if pixel_count == 0 {
+    return Err(invalid_data("empty film in checkpoint"));
+  }
+  if pixel_count.checked_mul(PIXEL_BYTES) != Some(remaining(&mut r, len)?) {
+    return Err(invalid_data("film size doesn't match the checkpoint"));
+  }
+
+  let mut pixels = Vec::with_capacity(pixel_count as usize);
+  for _ in 0..pixel_count {
+    let mut pixel = FilmPixel::empty();
+    pixel.contrib_sum =
+      Vec3::new(read_f32(&mut r)?, read_f32(&mut r)?, read_f32(&mut r)?);
+    pixel.filter_weight_sum = read_f32(&mut r)?;
+    pixel.sample_count = read_u32(&mut r)?;
+    pixels.push(pixel);
+  }
+
+  let film =
+    Film::from_pixels(width, height, filter.create(filter_radius), pixels);
+
+  Ok((
+    CheckpointHeader {
+      scene,
+      scene_seed,
+      filter,
+      filter_radius,
+      spp,
+      sampler,
+    },
+    film,
+  ))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::window::Window;
+
+  #[test]
+  fn test_checkpoint_round_trip() {
+    let kind = FilterKind::Tent;
+    let mut film = Film::new(3, 2, kind.create(1_f32));
+    let mut tile = film.tile(Window::new(0, 3, 0, 2));
+    tile.add_sample(1.2_f32, 0.7_f32, Vec3::new(1_f32, 2_f32, 3_f32));
+    film.merge_tile(&tile);
+
+    let header = CheckpointHeader {
+      scene: "cornell_box".to_string(),
+      scene_seed: 11,
+      filter: kind,
+      filter_radius: 1_f32,
+      spp: 16,
+      sampler: Sampler { seed: 7, pass: 5 },
+    };
+
+    let path = std::env::temp_dir()
+      .join(format!("raytracer-test-{}.ckpt", std::process::id()));
+    save(&path, &header, &film).unwrap();
+    let (loaded_header, loaded_film) = load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded_header, header);
+    assert_eq!(loaded_film.width, 3);
+    assert_eq!(loaded_film.height, 2);
+    assert_eq!(loaded_film.pixel(1, 0).sample_count, 1);
+    for (a, b) in film.pixels().iter().zip(loaded_film.pixels()) {
+      assert_eq!(a.contrib_sum, b.contrib_sum);
+      assert_eq!(a.filter_weight_sum, b.filter_weight_sum);
+      assert_eq!(a.sample_count, b.sample_count);
+    }
+  }
+
+  #[test]
+  fn test_corrupt_checkpoint_is_rejected() {
+    let film = Film::new(3, 2, FilterKind::Box.create(0.5_f32));
+    let header = CheckpointHeader {
+      scene: "cornell_box".to_string(),
+      scene_seed: 0,
+      filter: FilterKind::Box,
+      filter_radius: 0.5_f32,
+      spp: 1,
+      sampler: Sampler::new(0),
+    };
+
+    let path = std::env::temp_dir()
+      .join(format!("raytracer-corrupt-{}.ckpt", std::process::id()));
+    save(&path, &header, &film).unwrap();
+    let bytes = fs::read(&path).unwrap();
+
+    let load_bytes = |bytes: &[u8]| {
+      fs::write(&path, bytes).unwrap();
+      load(&path).map(|_| ()).map_err(|e| e.kind())
+    };
+
+    //
+    // A huge width, then a film cut off in the middle.
+    let mut huge = bytes.clone();
+    huge[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(load_bytes(&huge), Err(io::ErrorKind::InvalidData));
+    assert_eq!(
+      load_bytes(&bytes[..bytes.len() - 7]),
+      Err(io::ErrorKind::InvalidData)
+    );
+    assert_eq!(load_bytes(&bytes), Ok(()));
+    fs::remove_file(&path).unwrap();
+  }
+}
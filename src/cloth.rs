@@ -0,0 +1,201 @@
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::texture::Texture;
+use super::vec3::{dot_product, random_cosine_direction, unit_vector, Vec3};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// Fabric: a diffuse base under a layer of fibres that catch light at
+/// grazing angles, using the "Charlie" sheen distribution (Estevez and
+/// Kulla, "Production Friendly Microfacet Sheen BRDF", 2017) with
+/// Neubelt and Pettineo's visibility term.
+pub struct Cloth {
+  pub albedo: Arc<dyn Texture>,
+  pub sheen_color: Arc<dyn Texture>,
+  /// In (0, 1]; low values give a tight rim like velvet, high values a
+  /// broad satin-like haze. Fixed at construction, as `sheen_albedo`
+  /// depends on it.
+  sheen_roughness: f32,
+  /// Fraction of light a white sheen reflects, by the cosine of the view
+  /// angle from 0 to 1 in even steps.
+  sheen_albedo: Vec<f32>,
+}
+
+/// Below this the distribution is too peaked to evaluate reliably.
+const MIN_SHEEN_ROUGHNESS: f32 = 0.07_f32;
+
+/// Entries in the sheen albedo table.
+const ALBEDO_STEPS: usize = 32;
+
+impl Cloth {
+  pub fn new(
+    albedo: Arc<dyn Texture>,
+    sheen_color: Arc<dyn Texture>,
+    sheen_roughness: f32,
+  ) -> Cloth {
+    let mut cloth = Cloth {
+      albedo,
+      sheen_color,
+      sheen_roughness: sheen_roughness.clamp(MIN_SHEEN_ROUGHNESS, 1_f32),
+      sheen_albedo: Vec::new(),
+    };
+    cloth.sheen_albedo = (0..ALBEDO_STEPS)
+      .map(|i| cloth.directional_albedo(i as f32 / (ALBEDO_STEPS - 1) as f32))
+      .collect();
+    cloth
+  }
+
+  /// Integral of the sheen lobe over the hemisphere for light leaving at
+  /// `cos_o` to the normal, by the midpoint rule.
+  fn directional_albedo(&self, cos_o: f32) -> f32 {
+    let wo = Vec3::new((1_f32 - cos_o * cos_o).sqrt(), 0_f32, cos_o);
+    let steps = 32;
+    let d_theta = 0.5_f32 * PI / steps as f32;
+    let d_phi = 2_f32 * PI / steps as f32;
+
+    let mut sum = 0_f32;
+    for i in 0..steps {
+      let theta = (i as f32 + 0.5_f32) * d_theta;
+      let (sin_t, cos_t) = theta.sin_cos();
+      for j in 0..steps {
+        let phi = (j as f32 + 0.5_f32) * d_phi;
+        let wi = Vec3::new(sin_t * phi.cos(), sin_t * phi.sin(), cos_t);
+        let m = unit_vector(wo + wi);
+        sum += self.distribution(m.z)
+          * Cloth::visibility(cos_o, cos_t)
+          * cos_t
+          * sin_t;
+      }
+    }
+
+    sum * d_theta * d_phi
+  }
+
+  /// `sheen_albedo` at `cos_o`, interpolated.
+  fn sheen_albedo_at(&self, cos_o: f32) -> f32 {
+    let x = cos_o.clamp(0_f32, 1_f32) * (ALBEDO_STEPS - 1) as f32;
+    let i = (x as usize).min(ALBEDO_STEPS - 2);
+    let t = x - i as f32;
+    (1_f32 - t) * self.sheen_albedo[i] + t * self.sheen_albedo[i + 1]
+  }
+
+  /// Density of fibre normals, `cos_h` the cosine of the half vector to
+  /// the normal.
+  fn distribution(&self, cos_h: f32) -> f32 {
+    let inv_r = 1_f32 / self.sheen_roughness;
+    let sin2 = (1_f32 - cos_h * cos_h).max(0_f32);
+
+    (2_f32 + inv_r) * sin2.powf(0.5_f32 * inv_r) / (2_f32 * PI)
+  }
+
+  fn visibility(cos_o: f32, cos_i: f32) -> f32 {
+    1_f32 / (4_f32 * (cos_i + cos_o - cos_i * cos_o))
+  }
+}
+
+impl Material for Cloth {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    let n = if dot_product(r.direction, h.normal) < 0_f32 {
+      h.normal
+    } else {
+      -h.normal
+    };
+
+    let frame = Onb::from_w(n);
+    let wo = frame.to_local(-unit_vector(r.direction));
+    let wi = random_cosine_direction();
+    if wo.z <= 0_f32 || wi.z <= 0_f32 {
+      return None;
+    }
+
+    //
+    // The sheen takes its share of the light off the base below, so the
+    // two never reflect more than comes in. Where the lobe alone would,
+    // at grazing angles on low roughness, it is scaled down too.
+    let sheen_color = self.sheen_color.value_at(r, h);
+    let albedo = self.sheen_albedo_at(wo.z);
+    let sheen_max = sheen_color.x.max(sheen_color.y).max(sheen_color.z);
+    let base = (1_f32 - sheen_max.clamp(0_f32, 1_f32) * albedo.min(1_f32))
+      * self.albedo.value_at(r, h);
+
+    let m = unit_vector(wo + wi);
+    let sheen = sheen_color
+      * (self.distribution(m.z) * Cloth::visibility(wo.z, wi.z)
+        / albedo.max(1_f32));
+
+    //
+    // Cosine sampling turns f cos / pdf into pi f.
+    let attenuation = base + PI * sheen;
+
+    Some((attenuation, Ray::new(h.p, frame.to_world(wi), r.time)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+
+  #[test]
+  fn test_sheen_distribution_is_normalized() {
+    for &roughness in [0.2_f32, 0.5_f32, 1_f32].iter() {
+      let cloth = Cloth::new(
+        Arc::new(ConstantTexture::same(0_f32)),
+        Arc::new(ConstantTexture::same(1_f32)),
+        roughness,
+      );
+
+      //
+      // Projected onto the macro surface the fibres cover it once.
+      let steps = 4096;
+      let d_theta = 0.5_f32 * PI / steps as f32;
+      let integral: f32 = (0..steps)
+        .map(|i| {
+          let theta = (i as f32 + 0.5_f32) * d_theta;
+          cloth.distribution(theta.cos())
+            * theta.cos()
+            * theta.sin()
+            * 2_f32
+            * PI
+            * d_theta
+        })
+        .sum();
+
+      assert!((integral - 1_f32).abs() < 0.01_f32, "{}", integral);
+    }
+  }
+
+  #[test]
+  fn test_white_cloth_does_not_gain_energy() {
+    let white = || Arc::new(ConstantTexture::same(1_f32));
+    let up = Vec3::new(0_f32, 0_f32, 1_f32);
+
+    for &roughness in [0.07_f32, 0.3_f32, 1_f32].iter() {
+      let cloth = Arc::new(Cloth::new(white(), white(), roughness));
+      let h = HitRecord::new(
+        1_f32,
+        Vec3::same(0_f32),
+        up,
+        cloth.clone(),
+        0_f32,
+        0_f32,
+      );
+
+      //
+      // Average the throughput of many bounces at a few view angles.
+      for &cos_o in [0.05_f32, 0.4_f32, 1_f32].iter() {
+        let dir = Vec3::new((1_f32 - cos_o * cos_o).sqrt(), 0_f32, -cos_o);
+        let r = Ray::new(-dir, dir, 0_f32);
+
+        let n = 20000;
+        let mean = (0..n)
+          .map(|_| cloth.scatter(&r, &h).map_or(0_f32, |(a, _)| a.x))
+          .sum::<f32>()
+          / n as f32;
+        assert!(mean <= 1.02_f32, "{} {} {}", roughness, cos_o, mean);
+      }
+    }
+  }
+}
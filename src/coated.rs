@@ -0,0 +1,107 @@
+use super::dielectric::{absorption_from_transmittance, transmittance};
+use super::fresnel::fresnel_dielectric;
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::microfacet::GgxDistribution;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::vec3::{dot_product, reflect, unit_vector, Vec3};
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// A dielectric clear coat layered over any base material, like varnish
+/// on wood or the lacquer on car paint.
+///
+/// The coat reflects with its Fresnel probability; the rest of the light
+/// is handed to the base and pays the Fresnel transmission on the way out.
+/// Refraction through the layer is not modelled, the base sees the
+/// original ray.
+pub struct Coated {
+  pub base: Arc<dyn Material>,
+  pub ior: f32,
+  pub distribution: GgxDistribution,
+  /// Beer-Lambert absorption per unit of coat thickness.
+  pub absorption: Vec3,
+}
+
+impl Coated {
+  /// `roughness` is perceptually linear, 0 gives a mirror-like coat.
+  pub fn new(base: Arc<dyn Material>, ior: f32, roughness: f32) -> Coated {
+    Coated {
+      base,
+      ior,
+      distribution: GgxDistribution::isotropic(
+        GgxDistribution::roughness_to_alpha(roughness),
+      ),
+      absorption: Vec3::same(0_f32),
+    }
+  }
+
+  /// Tints the coat so that light crossing it straight down and back up
+  /// keeps `color`.
+  pub fn tinted(mut self, color: Vec3) -> Coated {
+    self.absorption = absorption_from_transmittance(color, 2_f32);
+    self
+  }
+
+  /// Cosine of the refracted direction inside the coat.
+  fn cos_inside(&self, cos: f32) -> f32 {
+    let sin2 = (1_f32 - cos * cos) / (self.ior * self.ior);
+    (1_f32 - sin2).max(1e-4_f32).sqrt()
+  }
+}
+
+impl Material for Coated {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    //
+    // Rays inside the object are below the coat already.
+    if dot_product(r.direction, h.normal) > 0_f32 {
+      return self.base.scatter(r, h);
+    }
+
+    let frame = Onb::from_w(h.normal);
+    let wo = frame.to_local(-unit_vector(r.direction));
+    if wo.z <= 0_f32 {
+      return None;
+    }
+
+    let mut rng = thread_rng();
+    let m = self
+      .distribution
+      .sample_visible_normal(wo, rng.gen(), rng.gen());
+    let fresnel = fresnel_dielectric(dot_product(wo, m), self.ior);
+
+    if rng.gen::<f32>() < fresnel {
+      let wi = reflect(-wo, m);
+      if wi.z <= 0_f32 {
+        return None;
+      }
+
+      let weight = self.distribution.g2(wo, wi) / self.distribution.g1(wo);
+      return Some((
+        Vec3::same(weight),
+        Ray::new(h.p, frame.to_world(wi), r.time),
+      ));
+    }
+
+    //
+    // Picking the base with probability 1 - F cancels the transmission
+    // into the coat, leaving the transmission out of it, which by
+    // reciprocity is the Fresnel term seen from outside.
+    let (attenuation, scattered) = self.base.scatter(r, h)?;
+    let cos_out = dot_product(unit_vector(scattered.direction), h.normal);
+    if cos_out <= 0_f32 {
+      return Some((attenuation, scattered));
+    }
+
+    let path = 1_f32 / self.cos_inside(wo.z) + 1_f32 / self.cos_inside(cos_out);
+    let exit = 1_f32 - fresnel_dielectric(cos_out, self.ior);
+    let absorbed = transmittance(self.absorption, path);
+
+    Some((attenuation * absorbed * exit, scattered))
+  }
+
+  fn emitted(&self, r: &Ray, h: &HitRecord) -> Vec3 {
+    self.base.emitted(r, h)
+  }
+}
@@ -0,0 +1,80 @@
+use super::vec3::Vec3;
+
+/// Piecewise linear gradient through colour stops, mapping a value in
+/// [0, 1] to a colour. Values outside the stops take the end colours, and
+/// NaN or infinite ones the first.
+#[derive(Clone, Debug)]
+pub struct ColorRamp {
+  stops: Vec<(f32, Vec3)>,
+}
+
+impl ColorRamp {
+  /// `stops` are (position, colour) pairs, in any order, at finite
+  /// positions.
+  pub fn new(stops: Vec<(f32, Vec3)>) -> ColorRamp {
+    assert!(!stops.is_empty(), "a colour ramp needs at least one stop");
+    assert!(
+      stops.iter().all(|s| s.0.is_finite()),
+      "colour stops must be at finite positions"
+    );
+    let mut stops = stops;
+    stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    ColorRamp { stops }
+  }
+
+  /// Black to white.
+  pub fn grayscale() -> ColorRamp {
+    ColorRamp::new(vec![(0_f32, Vec3::same(0_f32)), (1_f32, Vec3::same(1_f32))])
+  }
+
+  pub fn evaluate(&self, t: f32) -> Vec3 {
+    let first = self.stops[0];
+    let last = self.stops[self.stops.len() - 1];
+    if !t.is_finite() || t <= first.0 {
+      return first.1;
+    }
+    if t >= last.0 {
+      return last.1;
+    }
+
+    let i = self.stops.iter().position(|s| s.0 > t).unwrap();
+    let (t0, c0) = self.stops[i - 1];
+    let (t1, c1) = self.stops[i];
+    let f = (t - t0) / (t1 - t0);
+
+    (1_f32 - f) * c0 + f * c1
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_ramp_interpolates_between_stops() {
+    let red = Vec3::new(1_f32, 0_f32, 0_f32);
+    let blue = Vec3::new(0_f32, 0_f32, 1_f32);
+    let ramp = ColorRamp::new(vec![(0.75_f32, blue), (0.25_f32, red)]);
+
+    assert_eq!(ramp.evaluate(0_f32), red);
+    assert_eq!(ramp.evaluate(1_f32), blue);
+    assert!((ramp.evaluate(0.5_f32) - 0.5_f32 * (red + blue)).length() < 1e-6);
+  }
+
+  #[test]
+  fn test_ramp_takes_the_first_stop_for_nan() {
+    let ramp = ColorRamp::grayscale();
+    assert_eq!(ramp.evaluate(f32::NAN), Vec3::same(0_f32));
+    assert_eq!(ramp.evaluate(f32::INFINITY), Vec3::same(0_f32));
+  }
+
+  #[test]
+  #[should_panic(expected = "finite positions")]
+  fn test_ramp_rejects_a_nan_stop() {
+    ColorRamp::new(vec![
+      (0_f32, Vec3::same(0_f32)),
+      (f32::NAN, Vec3::same(1_f32)),
+    ]);
+  }
+}
@@ -0,0 +1,123 @@
+use super::fresnel::fresnel_conductor_rgb;
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::microfacet::GgxDistribution;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::vec3::{dot_product, reflect, unit_vector, Vec3};
+use rand::prelude::*;
+
+/// Physically based metal: GGX microfacets with Smith masking-shadowing and
+/// the Fresnel equations for a complex index of refraction `eta + i k`,
+/// given per RGB channel.
+///
+/// The distribution may be anisotropic, with `alpha_x` along the surface
+/// tangent and `alpha_y` along the bitangent.
+pub struct Conductor {
+  pub eta: Vec3,
+  pub k: Vec3,
+  pub distribution: GgxDistribution,
+  /// World space direction that replaces the surface tangent, projected
+  /// onto the tangent plane.
+  pub tangent: Option<Vec3>,
+}
+
+impl Conductor {
+  /// `roughness` is perceptually linear, 0 is a perfect mirror.
+  pub fn new(eta: Vec3, k: Vec3, roughness: f32) -> Conductor {
+    Conductor {
+      eta,
+      k,
+      distribution: GgxDistribution::isotropic(
+        GgxDistribution::roughness_to_alpha(roughness),
+      ),
+      tangent: None,
+    }
+  }
+
+  /// Gives the metal separate roughness along the tangent (`roughness_u`)
+  /// and across it (`roughness_v`), like brushed or machined metal.
+  pub fn anisotropic(
+    mut self,
+    roughness_u: f32,
+    roughness_v: f32,
+  ) -> Conductor {
+    self.distribution = GgxDistribution::new(
+      GgxDistribution::roughness_to_alpha(roughness_u),
+      GgxDistribution::roughness_to_alpha(roughness_v),
+    );
+    self
+  }
+
+  /// Orients the anisotropy along a fixed direction instead of the
+  /// surface's tangent.
+  pub fn with_tangent(mut self, tangent: Vec3) -> Conductor {
+    self.tangent = Some(tangent);
+    self
+  }
+
+  pub fn gold(roughness: f32) -> Conductor {
+    Conductor::new(
+      Vec3::new(0.143_f32, 0.374_f32, 1.442_f32),
+      Vec3::new(3.983_f32, 2.385_f32, 1.603_f32),
+      roughness,
+    )
+  }
+
+  pub fn copper(roughness: f32) -> Conductor {
+    Conductor::new(
+      Vec3::new(0.200_f32, 0.924_f32, 1.102_f32),
+      Vec3::new(3.912_f32, 2.452_f32, 2.142_f32),
+      roughness,
+    )
+  }
+
+  pub fn aluminium(roughness: f32) -> Conductor {
+    Conductor::new(
+      Vec3::new(1.657_f32, 0.880_f32, 0.521_f32),
+      Vec3::new(9.224_f32, 6.270_f32, 4.837_f32),
+      roughness,
+    )
+  }
+
+  pub fn silver(roughness: f32) -> Conductor {
+    Conductor::new(
+      Vec3::new(0.155_f32, 0.117_f32, 0.138_f32),
+      Vec3::new(4.828_f32, 3.122_f32, 2.147_f32),
+      roughness,
+    )
+  }
+}
+
+impl Material for Conductor {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    let n = if dot_product(r.direction, h.normal) < 0_f32 {
+      h.normal
+    } else {
+      -h.normal
+    };
+
+    let frame = Onb::from_w_u(n, self.tangent.unwrap_or(h.tangent));
+    let wo = frame.to_local(-unit_vector(r.direction));
+    if wo.z <= 0_f32 {
+      return None;
+    }
+
+    let mut rng = thread_rng();
+    let m = self
+      .distribution
+      .sample_visible_normal(wo, rng.gen(), rng.gen());
+    let wi = reflect(-wo, m);
+    if wi.z <= 0_f32 {
+      return None;
+    }
+
+    //
+    // With visible normal sampling the estimator reduces to F * G2 / G1.
+    let fresnel = fresnel_conductor_rgb(dot_product(wo, m), self.eta, self.k);
+    let attenuation =
+      fresnel * (self.distribution.g2(wo, wi) / self.distribution.g1(wo));
+
+    Some((attenuation, Ray::new(h.p, frame.to_world(wi), r.time)))
+  }
+}
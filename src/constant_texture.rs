@@ -1,5 +1,5 @@
 use super::vec3::Vec3;
-use super::texture::Texture;
+use super::texture::{Texture, TexturePoint};
 
 pub struct ConstantTexture {
   color : Vec3
@@ -9,10 +9,15 @@ impl ConstantTexture {
   pub fn new(c : Vec3) -> ConstantTexture {
     ConstantTexture{color : c}
   }
+
+  /// Grey texture, handy for scalar parameters such as roughness.
+  pub fn same(v : f32) -> ConstantTexture {
+    ConstantTexture::new(Vec3::same(v))
+  }
 }
 
 impl Texture for ConstantTexture {
-  fn value(&self, _u : f32, _v : f32, _p : Vec3) -> Vec3 {
+  fn lookup(&self, _at : &TexturePoint) -> Vec3 {
     self.color
   }
 }
\ No newline at end of file
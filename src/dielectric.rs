@@ -1,51 +1,165 @@
+use super::fresnel::fresnel_dielectric;
 use super::hitable::HitRecord;
 use super::material::Material;
 use super::ray::Ray;
-use super::vec3::{dot_product, reflect, refract, schlick, Vec3};
+use super::ray_differential;
+use super::thin_film::ThinFilm;
+use super::vec3::{dot_product, reflect, refract, unit_vector, Vec3};
 use rand::prelude::*;
 
 pub struct Dielectric {
   pub ref_idx: f32,
+  /// Beer-Lambert absorption coefficient per unit of distance travelled
+  /// inside the medium, per channel. Zero is perfectly clear.
+  pub absorption: Vec3,
+  pub thin_film: Option<ThinFilm>,
+}
+
+/// Absorption coefficient that leaves `color` of the light after it
+/// travels `distance` through the medium. Channels are clamped to (0, 1],
+/// as a medium can't add light or absorb all of it; `distance` must be
+/// positive.
+pub fn absorption_from_transmittance(color: Vec3, distance: f32) -> Vec3 {
+  assert!(distance > 0_f32, "transmittance distance must be positive");
+  let coefficient = |c: f32| -c.clamp(1e-6_f32, 1_f32).ln() / distance;
+  Vec3::new(coefficient(color.x), coefficient(color.y), coefficient(color.z))
+}
+
+/// Fraction of the light left after travelling `distance` through a medium
+/// with the given absorption coefficient.
+pub fn transmittance(absorption: Vec3, distance: f32) -> Vec3 {
+  Vec3::new(
+    (-absorption.x * distance).exp(),
+    (-absorption.y * distance).exp(),
+    (-absorption.z * distance).exp(),
+  )
+}
+
+/// Attenuation for a ray that reached `h` from inside a medium with the
+/// given absorption coefficient, or no attenuation if it came from outside.
+pub fn interior_attenuation(r: &Ray, h: &HitRecord, absorption: Vec3) -> Vec3 {
+  if dot_product(r.direction, h.normal) > 0f32 {
+    transmittance(absorption, h.t * r.direction.length())
+  } else {
+    Vec3::same(1f32)
+  }
 }
 
 impl Dielectric {
   pub fn new(ri: f32) -> Dielectric {
-    Dielectric { ref_idx: ri }
+    Dielectric {
+      ref_idx: ri,
+      absorption: Vec3::same(0f32),
+      thin_film: None,
+    }
+  }
+
+  /// Coloured glass or liquid that lets `color` of the light through
+  /// after `distance` units.
+  pub fn tinted(ri: f32, color: Vec3, distance: f32) -> Dielectric {
+    Dielectric {
+      ref_idx: ri,
+      absorption: absorption_from_transmittance(color, distance),
+      thin_film: None,
+    }
+  }
+
+  /// Coats the surface with an interference film; a dielectric with an
+  /// index of 1 and a film of water makes a soap bubble.
+  pub fn with_thin_film(mut self, film: ThinFilm) -> Dielectric {
+    self.thin_film = Some(film);
+    self
   }
 }
 
 impl Material for Dielectric {
   fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
-    let reflected = reflect(r.direction, h.normal);
-    let attenuation = Vec3::same(1_f32);
-
-    let (outward_normal, ni_over_nt, cosine) =
-      if dot_product(r.direction, h.normal) > 0f32 {
-        (
-          -h.normal,
-          self.ref_idx,
-          self.ref_idx * dot_product(r.direction, h.normal)
-            / r.direction.length(),
-        )
-      } else {
-        (
-          h.normal,
-          1f32 / self.ref_idx,
-          -dot_product(r.direction, h.normal) / r.direction.length(),
-        )
-      };
+    let reflected = || {
+      Ray::new(h.p, reflect(r.direction, h.normal), r.time)
+        .with_differential(ray_differential::reflected(r, h))
+    };
+    let attenuation = interior_attenuation(r, h, self.absorption);
+    let unit_direction = unit_vector(r.direction);
 
-    let (refracted, reflect_prob) =
-      if let Some(refr) = refract(r.direction, outward_normal, ni_over_nt) {
-        (Some(refr), schlick(cosine, self.ref_idx))
-      } else {
-        (None, 1f32)
-      };
+    //
+    // eta is the ratio of the index on the far side to the near one.
+    let (outward_normal, eta) = if dot_product(unit_direction, h.normal) > 0f32
+    {
+      (-h.normal, 1f32 / self.ref_idx)
+    } else {
+      (h.normal, self.ref_idx)
+    };
+
+    let cosine = -dot_product(unit_direction, outward_normal);
+
+    let refracted = match refract(r.direction, outward_normal, 1f32 / eta) {
+      Some(refracted) => refracted,
+      None => return Some((attenuation, reflected())),
+    };
+
+    //
+    // A film makes the reflectance differ per channel. Reflecting with the
+    // mean reflectance and weighting each channel keeps it unbiased.
+    let reflectance = match &self.thin_film {
+      Some(film) => {
+        let (outside, inside) = if eta > 1f32 {
+          (1f32, self.ref_idx)
+        } else {
+          (self.ref_idx, 1f32)
+        };
+        film.reflectance(
+          cosine,
+          film.thickness_at(r, h),
+          outside,
+          Vec3::same(inside),
+          Vec3::same(0f32),
+        )
+      }
+      None => Vec3::same(fresnel_dielectric(cosine, eta)),
+    };
+    let reflect_prob = (reflectance.x + reflectance.y + reflectance.z) / 3f32;
 
     if thread_rng().gen::<f32>() < reflect_prob {
-      Some((attenuation, Ray::new(h.p, reflected, r.time)))
+      Some((attenuation * reflectance / reflect_prob, reflected()))
     } else {
-      Some((attenuation, Ray::new(h.p, refracted.unwrap(), r.time)))
+      Some((
+        attenuation * (Vec3::same(1f32) - reflectance) / (1f32 - reflect_prob),
+        Ray::new(h.p, refracted, r.time).with_differential(
+          ray_differential::refracted(r, h, 1f32 / eta),
+        ),
+      ))
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_transmittance_matches_reference_distance() {
+    let color = Vec3::new(0.9_f32, 0.5_f32, 0.1_f32);
+    let absorption = absorption_from_transmittance(color, 2_f32);
+
+    let t = transmittance(absorption, 2_f32);
+    assert!((t - color).length() < 1e-5_f32);
+
+    let t = transmittance(absorption, 4_f32);
+    assert!((t - color * color).length() < 1e-5_f32);
+  }
+
+  #[test]
+  fn test_transmittance_is_clamped() {
+    let absorption =
+      absorption_from_transmittance(Vec3::new(1.5_f32, 0_f32, 1_f32), 1_f32);
+    assert_eq!(absorption.x, 0_f32);
+    assert!(absorption.y.is_finite() && absorption.y > 0_f32);
+    assert_eq!(absorption.z, 0_f32);
+  }
+
+  #[test]
+  #[should_panic]
+  fn test_transmittance_needs_a_distance() {
+    absorption_from_transmittance(Vec3::same(0.5_f32), 0_f32);
+  }
+}
@@ -2,16 +2,68 @@ use super::hitable::HitRecord;
 use super::material::Material;
 use super::ray::Ray;
 use super::texture::Texture;
-use super::vec3::Vec3;
+use super::vec3::{dot_product, Vec3};
+use std::f32::consts::PI;
 use std::sync::Arc;
 
+/// How bright an emitter is.
+#[derive(Copy, Clone, Debug)]
+pub enum Intensity {
+  /// Multiplies the colour, giving radiance directly.
+  Radiance(f32),
+  /// Total radiant flux leaving a surface of the given area, spread over
+  /// its emitting sides.
+  Power { flux: f32, area: f32 },
+}
+
 pub struct DiffuseLight {
-  emit: Arc<Texture>,
+  color: Arc<dyn Texture>,
+  intensity: Intensity,
+  two_sided: bool,
 }
 
 impl DiffuseLight {
-  pub fn new(tex: Arc<Texture>) -> DiffuseLight {
-    DiffuseLight { emit: tex }
+  /// A two-sided emitter with radiance given by `tex`.
+  pub fn new(tex: Arc<dyn Texture>) -> DiffuseLight {
+    DiffuseLight {
+      color: tex,
+      intensity: Intensity::Radiance(1_f32),
+      two_sided: true,
+    }
+  }
+
+  /// Emits only on the side the surface normal points to.
+  pub fn one_sided(mut self) -> DiffuseLight {
+    self.two_sided = false;
+    self
+  }
+
+  /// Scales the colour to radiance.
+  pub fn with_scale(mut self, scale: f32) -> DiffuseLight {
+    self.intensity = Intensity::Radiance(scale);
+    self
+  }
+
+  /// Sets the emitted flux, in the scene's power units, for a shape of
+  /// the given area (see `Sphere::area` and `XZRect::area`). A white colour
+  /// emits exactly `flux`.
+  pub fn with_power(mut self, flux: f32, area: f32) -> DiffuseLight {
+    self.intensity = Intensity::Power { flux, area };
+    self
+  }
+
+  /// Factor from colour to radiance.
+  fn scale(&self) -> f32 {
+    match self.intensity {
+      Intensity::Radiance(scale) => scale,
+      Intensity::Power { flux, area } => {
+        //
+        // A Lambertian emitter with radiance L sends out pi L per unit of
+        // area on each side.
+        let sides = if self.two_sided { 2_f32 } else { 1_f32 };
+        flux / (PI * area * sides)
+      }
+    }
   }
 }
 
@@ -20,7 +72,27 @@ impl Material for DiffuseLight {
     None
   }
 
-  fn emitted(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
-    self.emit.value(u, v, p)
+  fn emitted(&self, r: &Ray, h: &HitRecord) -> Vec3 {
+    if !self.two_sided && dot_product(r.direction, h.normal) >= 0_f32 {
+      return Vec3::same(0_f32);
+    }
+
+    self.color.value_at(r, h) * self.scale()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+
+  #[test]
+  fn test_power_to_radiance() {
+    let white = || Arc::new(ConstantTexture::same(1_f32));
+    let light = DiffuseLight::new(white()).with_power(100_f32, 2_f32);
+    assert!((light.scale() - 100_f32 / (4_f32 * PI)).abs() < 1e-5_f32);
+
+    let light = light.one_sided();
+    assert!((light.scale() - 100_f32 / (2_f32 * PI)).abs() < 1e-5_f32);
   }
 }
@@ -0,0 +1,226 @@
+use super::filter::Filter;
+use super::vec3::{to_rgb8, Vec3};
+use super::window::Window;
+use rgb::RGB8;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug)]
+pub struct FilmPixel {
+  pub contrib_sum: Vec3,
+  pub filter_weight_sum: f32,
+  /// Number of samples taken inside this pixel. Samples splatted in from
+  /// neighbouring pixels are not counted.
+  pub sample_count: u32,
+}
+
+impl FilmPixel {
+  pub fn empty() -> FilmPixel {
+    FilmPixel {
+      contrib_sum: Vec3::same(0_f32),
+      filter_weight_sum: 0_f32,
+      sample_count: 0,
+    }
+  }
+
+  pub fn color(&self) -> Vec3 {
+    if self.filter_weight_sum == 0_f32 {
+      return Vec3::same(0_f32);
+    }
+
+    let c = self.contrib_sum / self.filter_weight_sum;
+
+    //
+    // Filters with negative lobes can push a pixel below zero.
+    Vec3::new(c.x.max(0_f32), c.y.max(0_f32), c.z.max(0_f32))
+  }
+}
+
+/// Accumulates filtered radiance samples for the whole image. Pixel (0, 0)
+/// is the bottom left corner, matching the camera's (u, v) parametrization.
+pub struct Film {
+  pub width: u32,
+  pub height: u32,
+  filter: Arc<dyn Filter>,
+  pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+  pub fn new(width: u32, height: u32, filter: Arc<dyn Filter>) -> Film {
+    Film {
+      width,
+      height,
+      filter,
+      pixels: vec![FilmPixel::empty(); (width * height) as usize],
+    }
+  }
+
+  /// Rebuilds a film from previously accumulated pixels, stored bottom row
+  /// first.
+  pub fn from_pixels(
+    width: u32,
+    height: u32,
+    filter: Arc<dyn Filter>,
+    pixels: Vec<FilmPixel>,
+  ) -> Film {
+    assert_eq!(pixels.len(), (width * height) as usize);
+
+    Film {
+      width,
+      height,
+      filter,
+      pixels,
+    }
+  }
+
+  pub fn pixels(&self) -> &[FilmPixel] {
+    &self.pixels
+  }
+
+  pub fn filter(&self) -> Arc<dyn Filter> {
+    self.filter.clone()
+  }
+
+  pub fn tile(&self, bounds: Window<u32>) -> FilmTile {
+    FilmTile::new(bounds, self.filter.clone(), self.width, self.height)
+  }
+
+  pub fn merge_tile(&mut self, tile: &FilmTile) {
+    let mut idx = 0;
+    for y in tile.bounds.ymin..tile.bounds.ymax {
+      for x in tile.bounds.xmin..tile.bounds.xmax {
+        let src = &tile.pixels[idx];
+        let dst = &mut self.pixels[(y * self.width + x) as usize];
+        dst.contrib_sum += src.contrib_sum;
+        dst.filter_weight_sum += src.filter_weight_sum;
+        dst.sample_count += src.sample_count;
+        idx += 1;
+      }
+    }
+  }
+
+  pub fn pixel(&self, x: u32, y: u32) -> &FilmPixel {
+    &self.pixels[(y * self.width + x) as usize]
+  }
+
+  /// Resolves the film into gamma corrected, top-down image rows.
+  pub fn to_rgb8(&self) -> Vec<RGB8> {
+    let mut image = Vec::with_capacity(self.pixels.len());
+    for y in (0..self.height).rev() {
+      for x in 0..self.width {
+        let c = self.pixel(x, y).color();
+        image.push(to_rgb8(Vec3::new(c.x.sqrt(), c.y.sqrt(), c.z.sqrt())));
+      }
+    }
+
+    image
+  }
+}
+
+pub struct FilmTile {
+  pub bounds: Window<u32>,
+  filter: Arc<dyn Filter>,
+  pixels: Vec<FilmPixel>,
+}
+
+impl FilmTile {
+  /// Creates a tile that receives the samples taken inside `bounds`. The
+  /// tile is grown by the filter radius so that samples near the edge can
+  /// reach pixels owned by neighbouring tiles.
+  pub fn new(
+    bounds: Window<u32>,
+    filter: Arc<dyn Filter>,
+    film_width: u32,
+    film_height: u32,
+  ) -> FilmTile {
+    let r = filter.radius().ceil() as u32;
+    let padded = Window::new(
+      bounds.xmin.saturating_sub(r),
+      (bounds.xmax + r).min(film_width),
+      bounds.ymin.saturating_sub(r),
+      (bounds.ymax + r).min(film_height),
+    );
+
+    FilmTile {
+      bounds: padded,
+      filter,
+      pixels: vec![FilmPixel::empty(); padded.size() as usize],
+    }
+  }
+
+  /// Splats a sample taken at continuous raster position (`px`, `py`) into
+  /// every pixel whose center lies within the filter's support.
+  pub fn add_sample(&mut self, px: f32, py: f32, radiance: Vec3) {
+    let radius = self.filter.radius();
+
+    //
+    // Pixel centers sit at half integer coordinates.
+    let dx = px - 0.5_f32;
+    let dy = py - 0.5_f32;
+    let x0 = ((dx - radius).ceil() as i64).max(self.bounds.xmin as i64);
+    let x1 = ((dx + radius).floor() as i64).min(self.bounds.xmax as i64 - 1);
+    let y0 = ((dy - radius).ceil() as i64).max(self.bounds.ymin as i64);
+    let y1 = ((dy + radius).floor() as i64).min(self.bounds.ymax as i64 - 1);
+
+    let tile_width = self.bounds.width() as i64;
+
+    let (sx, sy) = (px.floor() as i64, py.floor() as i64);
+    if sx >= self.bounds.xmin as i64
+      && sx < self.bounds.xmax as i64
+      && sy >= self.bounds.ymin as i64
+      && sy < self.bounds.ymax as i64
+    {
+      let idx = (sy - self.bounds.ymin as i64) * tile_width
+        + (sx - self.bounds.xmin as i64);
+      self.pixels[idx as usize].sample_count += 1;
+    }
+
+    for y in y0..=y1 {
+      for x in x0..=x1 {
+        let weight = self.filter.evaluate(x as f32 - dx, y as f32 - dy);
+        let idx = (y - self.bounds.ymin as i64) * tile_width
+          + (x - self.bounds.xmin as i64);
+        let pixel = &mut self.pixels[idx as usize];
+        pixel.contrib_sum += radiance * weight;
+        pixel.filter_weight_sum += weight;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::filter::{BoxFilter, TentFilter};
+
+  #[test]
+  fn test_box_filter_reproduces_pixel_average() {
+    let mut film = Film::new(2, 2, Arc::new(BoxFilter::new(0.5_f32)));
+    let mut tile = film.tile(Window::new(0, 2, 0, 2));
+    tile.add_sample(0.25_f32, 0.25_f32, Vec3::same(1_f32));
+    tile.add_sample(0.75_f32, 0.75_f32, Vec3::same(3_f32));
+    film.merge_tile(&tile);
+
+    assert_eq!(film.pixel(0, 0).color(), Vec3::same(2_f32));
+    assert_eq!(film.pixel(1, 1).filter_weight_sum, 0_f32);
+  }
+
+  #[test]
+  fn test_samples_splat_across_tile_borders() {
+    let mut film = Film::new(4, 1, Arc::new(TentFilter::new(1_f32)));
+    let mut left = film.tile(Window::new(0, 2, 0, 1));
+    let right = film.tile(Window::new(2, 4, 0, 1));
+
+    //
+    // The padded tiles overlap on the pixels next to the shared border.
+    assert_eq!(left.bounds.xmax, 3);
+    assert_eq!(right.bounds.xmin, 1);
+
+    left.add_sample(1.9_f32, 0.5_f32, Vec3::same(1_f32));
+    film.merge_tile(&left);
+    film.merge_tile(&right);
+
+    assert!(film.pixel(1, 0).filter_weight_sum > 0_f32);
+    assert!(film.pixel(2, 0).filter_weight_sum > 0_f32);
+    assert_eq!(film.pixel(3, 0).filter_weight_sum, 0_f32);
+  }
+}
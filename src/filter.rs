@@ -0,0 +1,247 @@
+use std::f32::consts::PI;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Pixel reconstruction filter, evaluated at an offset from a pixel center
+/// (in pixel units). The filter is zero outside `[-radius, radius]` on both
+/// axes.
+pub trait Filter: Send + Sync {
+  fn radius(&self) -> f32;
+  fn evaluate(&self, x: f32, y: f32) -> f32;
+}
+
+pub struct BoxFilter {
+  radius: f32,
+}
+
+impl BoxFilter {
+  pub fn new(radius: f32) -> BoxFilter {
+    BoxFilter { radius }
+  }
+}
+
+impl Filter for BoxFilter {
+  fn radius(&self) -> f32 {
+    self.radius
+  }
+
+  fn evaluate(&self, _x: f32, _y: f32) -> f32 {
+    1_f32
+  }
+}
+
+pub struct TentFilter {
+  radius: f32,
+}
+
+impl TentFilter {
+  pub fn new(radius: f32) -> TentFilter {
+    TentFilter { radius }
+  }
+}
+
+impl Filter for TentFilter {
+  fn radius(&self) -> f32 {
+    self.radius
+  }
+
+  fn evaluate(&self, x: f32, y: f32) -> f32 {
+    (self.radius - x.abs()).max(0_f32) * (self.radius - y.abs()).max(0_f32)
+  }
+}
+
+pub struct GaussianFilter {
+  radius: f32,
+  alpha: f32,
+  exp_at_radius: f32,
+}
+
+impl GaussianFilter {
+  pub fn new(radius: f32, alpha: f32) -> GaussianFilter {
+    GaussianFilter {
+      radius,
+      alpha,
+      exp_at_radius: (-alpha * radius * radius).exp(),
+    }
+  }
+
+  fn gaussian(&self, d: f32) -> f32 {
+    ((-self.alpha * d * d).exp() - self.exp_at_radius).max(0_f32)
+  }
+}
+
+impl Filter for GaussianFilter {
+  fn radius(&self) -> f32 {
+    self.radius
+  }
+
+  fn evaluate(&self, x: f32, y: f32) -> f32 {
+    self.gaussian(x) * self.gaussian(y)
+  }
+}
+
+/// Mitchell-Netravali cubic. `b = c = 1/3` is the combination recommended
+/// by the original paper.
+pub struct MitchellFilter {
+  radius: f32,
+  b: f32,
+  c: f32,
+}
+
+impl MitchellFilter {
+  pub fn new(radius: f32, b: f32, c: f32) -> MitchellFilter {
+    MitchellFilter { radius, b, c }
+  }
+
+  fn mitchell_1d(&self, x: f32) -> f32 {
+    let (b, c) = (self.b, self.c);
+    let x = (2_f32 * x / self.radius).abs();
+
+    let w = if x > 1_f32 {
+      (-b - 6_f32 * c) * x * x * x
+        + (6_f32 * b + 30_f32 * c) * x * x
+        + (-12_f32 * b - 48_f32 * c) * x
+        + (8_f32 * b + 24_f32 * c)
+    } else {
+      (12_f32 - 9_f32 * b - 6_f32 * c) * x * x * x
+        + (-18_f32 + 12_f32 * b + 6_f32 * c) * x * x
+        + (6_f32 - 2_f32 * b)
+    };
+
+    w / 6_f32
+  }
+}
+
+impl Filter for MitchellFilter {
+  fn radius(&self) -> f32 {
+    self.radius
+  }
+
+  fn evaluate(&self, x: f32, y: f32) -> f32 {
+    self.mitchell_1d(x) * self.mitchell_1d(y)
+  }
+}
+
+/// Windowed sinc. `tau` is the number of sinc lobes kept inside the radius.
+pub struct LanczosFilter {
+  radius: f32,
+  tau: f32,
+}
+
+impl LanczosFilter {
+  pub fn new(radius: f32, tau: f32) -> LanczosFilter {
+    LanczosFilter { radius, tau }
+  }
+
+  fn windowed_sinc(&self, x: f32) -> f32 {
+    let x = x.abs();
+    if x > self.radius {
+      return 0_f32;
+    }
+
+    let lanczos = sinc(x / self.tau);
+    sinc(x) * lanczos
+  }
+}
+
+fn sinc(x: f32) -> f32 {
+  let x = x.abs();
+  if x < 1e-5_f32 {
+    1_f32
+  } else {
+    (PI * x).sin() / (PI * x)
+  }
+}
+
+impl Filter for LanczosFilter {
+  fn radius(&self) -> f32 {
+    self.radius
+  }
+
+  fn evaluate(&self, x: f32, y: f32) -> f32 {
+    self.windowed_sinc(x) * self.windowed_sinc(y)
+  }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FilterKind {
+  Box,
+  Tent,
+  Gaussian,
+  Mitchell,
+  Lanczos,
+}
+
+impl FilterKind {
+  pub fn default_radius(self) -> f32 {
+    match self {
+      FilterKind::Box => 0.5_f32,
+      FilterKind::Tent => 1_f32,
+      FilterKind::Gaussian => 1.5_f32,
+      FilterKind::Mitchell => 2_f32,
+      FilterKind::Lanczos => 3_f32,
+    }
+  }
+
+  pub fn create(self, radius: f32) -> Arc<dyn Filter> {
+    match self {
+      FilterKind::Box => Arc::new(BoxFilter::new(radius)),
+      FilterKind::Tent => Arc::new(TentFilter::new(radius)),
+      FilterKind::Gaussian => Arc::new(GaussianFilter::new(radius, 2_f32)),
+      FilterKind::Mitchell => Arc::new(MitchellFilter::new(
+        radius,
+        1_f32 / 3_f32,
+        1_f32 / 3_f32,
+      )),
+      FilterKind::Lanczos => Arc::new(LanczosFilter::new(radius, radius)),
+    }
+  }
+}
+
+impl FromStr for FilterKind {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<FilterKind, String> {
+    match s {
+      "box" => Ok(FilterKind::Box),
+      "tent" | "triangle" => Ok(FilterKind::Tent),
+      "gaussian" => Ok(FilterKind::Gaussian),
+      "mitchell" => Ok(FilterKind::Mitchell),
+      "lanczos" | "sinc" => Ok(FilterKind::Lanczos),
+      _ => Err(format!("unknown filter \"{}\"", s)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_filters_peak_at_center() {
+    let kinds = [
+      FilterKind::Tent,
+      FilterKind::Gaussian,
+      FilterKind::Mitchell,
+      FilterKind::Lanczos,
+    ];
+
+    for kind in kinds.iter() {
+      let f = kind.create(kind.default_radius());
+      let center = f.evaluate(0_f32, 0_f32);
+      assert!(center > 0_f32, "{:?}", kind);
+      assert!(center >= f.evaluate(0.25_f32, 0_f32), "{:?}", kind);
+      assert!(center >= f.evaluate(0.25_f32, -0.25_f32), "{:?}", kind);
+    }
+  }
+
+  #[test]
+  fn test_filters_vanish_at_radius() {
+    for kind in [FilterKind::Tent, FilterKind::Gaussian, FilterKind::Mitchell]
+      .iter()
+    {
+      let f = kind.create(kind.default_radius());
+      assert!(f.evaluate(f.radius(), 0_f32).abs() < 1e-4_f32, "{:?}", kind);
+    }
+  }
+}
@@ -4,29 +4,24 @@ use super::ray::Ray;
 use std::sync::Arc;
 
 pub struct FlipNormals {
-  obj: Arc<Hitable>,
+  obj: Arc<dyn Hitable>,
 }
 
 impl FlipNormals {
-  pub fn new(obj: Arc<Hitable>) -> FlipNormals {
+  pub fn new(obj: Arc<dyn Hitable>) -> FlipNormals {
     FlipNormals { obj }
   }
 }
 
 impl Hitable for FlipNormals {
   fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-    if let Some(hit) = self.obj.hit(r, t_min, t_max) {
-      Some(HitRecord::new(
-        hit.t,
-        hit.p,
-        hit.normal * -1f32,
-        hit.mtl.clone(),
-        hit.u,
-        hit.v,
-      ))
-    } else {
-      None
-    }
+    self.obj.hit(r, t_min, t_max).map(|mut hit| {
+      hit.normal *= -1f32;
+      hit.geometric_normal *= -1f32;
+      hit.dndu *= -1f32;
+      hit.dndv *= -1f32;
+      hit
+    })
   }
 
   fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
@@ -0,0 +1,125 @@
+use super::perlin::PerlinNoise;
+use super::vec3::Vec3;
+
+/// Perlin noise summed over octaves. Each octave multiplies the frequency
+/// by `lacunarity` and the amplitude by `gain`.
+pub struct FractalNoise {
+  perlin: PerlinNoise,
+  pub octaves: u32,
+  pub lacunarity: f32,
+  pub gain: f32,
+  /// Frequency of the first octave, in cycles per world unit.
+  pub frequency: f32,
+}
+
+impl FractalNoise {
+  pub fn new(seed: u64) -> FractalNoise {
+    FractalNoise {
+      perlin: PerlinNoise::with_seed(seed),
+      octaves: 6,
+      lacunarity: 2_f32,
+      gain: 0.5_f32,
+      frequency: 1_f32,
+    }
+  }
+
+  pub fn with_octaves(mut self, octaves: u32) -> FractalNoise {
+    self.octaves = octaves.max(1);
+    self
+  }
+
+  pub fn with_lacunarity(mut self, lacunarity: f32) -> FractalNoise {
+    self.lacunarity = lacunarity;
+    self
+  }
+
+  pub fn with_gain(mut self, gain: f32) -> FractalNoise {
+    self.gain = gain;
+    self
+  }
+
+  pub fn with_frequency(mut self, frequency: f32) -> FractalNoise {
+    self.frequency = frequency;
+    self
+  }
+
+  /// Sums `octave(noise, weight of the previous octave)` over the octaves
+  /// and normalizes by the total amplitude.
+  fn sum<F: FnMut(f32, f32) -> f32>(&self, p: Vec3, mut octave: F) -> f32 {
+    let mut p = self.frequency * p;
+    let mut amplitude = 1_f32;
+    let mut total = 0_f32;
+    let mut norm = 0_f32;
+    let mut previous = 1_f32;
+
+    for _ in 0..self.octaves {
+      previous = octave(self.perlin.noise(p), previous);
+      total += amplitude * previous;
+      norm += amplitude;
+      amplitude *= self.gain;
+      p *= self.lacunarity;
+    }
+
+    total / norm
+  }
+
+  /// Fractional Brownian motion, roughly in [-1, 1].
+  pub fn fbm(&self, p: Vec3) -> f32 {
+    self.sum(p, |n, _| n)
+  }
+
+  /// Sum of absolute octaves, in [0, 1], with creases where the noise
+  /// changes sign.
+  pub fn turbulence(&self, p: Vec3) -> f32 {
+    self.sum(p, |n, _| n.abs())
+  }
+
+  /// Musgrave's ridged multifractal, in [0, 1]: sharp ridges where the
+  /// noise crosses zero, with detail building up along the ridges.
+  pub fn ridged(&self, p: Vec3) -> f32 {
+    self.sum(p, |n, previous| {
+      let signal = 1_f32 - n.abs();
+      signal * signal * previous.clamp(0_f32, 1_f32)
+    })
+  }
+
+  /// Offsets `p` by fBm in each axis, scaled by `strength`, for swirled
+  /// domain warped patterns.
+  pub fn warp(&self, p: Vec3, strength: f32) -> Vec3 {
+    //
+    // Arbitrary offsets decorrelate the three axes.
+    let offset = Vec3::new(
+      self.fbm(p),
+      self.fbm(p + Vec3::new(5.2_f32, 1.3_f32, 2.8_f32)),
+      self.fbm(p + Vec3::new(1.7_f32, 9.2_f32, 4.1_f32)),
+    );
+    p + strength * offset
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_seed_fixes_the_pattern() {
+    let p = Vec3::new(0.3_f32, 1.7_f32, -2.1_f32);
+    let a = FractalNoise::new(7);
+    let b = FractalNoise::new(7);
+    let c = FractalNoise::new(8);
+
+    assert_eq!(a.fbm(p), b.fbm(p));
+    assert_ne!(a.fbm(p), c.fbm(p));
+  }
+
+  #[test]
+  fn test_ranges() {
+    let noise = FractalNoise::new(1).with_frequency(3_f32);
+    for i in 0..500 {
+      let p = Vec3::new(i as f32 * 0.137_f32, i as f32 * 0.071_f32, 0.5_f32);
+      assert!((0_f32..=1_f32).contains(&noise.turbulence(p)));
+      assert!((0_f32..=1_f32).contains(&noise.ridged(p)));
+      assert!(noise.fbm(p).abs() <= 1.5_f32);
+    }
+  }
+}
@@ -0,0 +1,89 @@
+use super::color_ramp::ColorRamp;
+use super::fractal_noise::FractalNoise;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::Vec3;
+
+/// fBm through a colour ramp, for clouds, dirt and general breakup.
+pub struct FbmTexture {
+  noise: FractalNoise,
+  ramp: ColorRamp,
+}
+
+impl FbmTexture {
+  pub fn new(noise: FractalNoise, ramp: ColorRamp) -> FbmTexture {
+    FbmTexture { noise, ramp }
+  }
+}
+
+impl Texture for FbmTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.ramp.evaluate(0.5_f32 + 0.5_f32 * self.noise.fbm(at.p))
+  }
+}
+
+/// Ridged multifractal noise through a colour ramp, for mountain ranges,
+/// veins and lightning-like patterns.
+pub struct RidgedTexture {
+  noise: FractalNoise,
+  ramp: ColorRamp,
+}
+
+impl RidgedTexture {
+  pub fn new(noise: FractalNoise, ramp: ColorRamp) -> RidgedTexture {
+    RidgedTexture { noise, ramp }
+  }
+}
+
+impl Texture for RidgedTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.ramp.evaluate(self.noise.ridged(at.p))
+  }
+}
+
+/// Turbulence through a colour ramp, for fire, smoke and billowy clouds.
+pub struct TurbulenceTexture {
+  noise: FractalNoise,
+  ramp: ColorRamp,
+}
+
+impl TurbulenceTexture {
+  pub fn new(noise: FractalNoise, ramp: ColorRamp) -> TurbulenceTexture {
+    TurbulenceTexture { noise, ramp }
+  }
+}
+
+impl Texture for TurbulenceTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.ramp.evaluate(self.noise.turbulence(at.p))
+  }
+}
+
+/// fBm evaluated at a point displaced by fBm, giving swirling, marbled
+/// flows.
+pub struct DomainWarpTexture {
+  noise: FractalNoise,
+  /// Displacement in world units per unit of noise.
+  strength: f32,
+  ramp: ColorRamp,
+}
+
+impl DomainWarpTexture {
+  pub fn new(
+    noise: FractalNoise,
+    strength: f32,
+    ramp: ColorRamp,
+  ) -> DomainWarpTexture {
+    DomainWarpTexture {
+      noise,
+      strength,
+      ramp,
+    }
+  }
+}
+
+impl Texture for DomainWarpTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    let q = self.noise.warp(at.p, self.strength);
+    self.ramp.evaluate(0.5_f32 + 0.5_f32 * self.noise.fbm(q))
+  }
+}
@@ -0,0 +1,82 @@
+use super::vec3::Vec3;
+
+/// Unpolarized Fresnel reflectance at a dielectric interface. `eta` is the
+/// ratio of the index of refraction on the far side of the interface to
+/// the one on the side the light arrives from, `cos_i` the cosine to the
+/// normal on the arriving side. Total internal reflection gives 1.
+pub fn fresnel_dielectric(cos_i: f32, eta: f32) -> f32 {
+  let cos_i = cos_i.clamp(0_f32, 1_f32);
+  let sin2_t = (1_f32 - cos_i * cos_i) / (eta * eta);
+  if sin2_t >= 1_f32 {
+    return 1_f32;
+  }
+
+  let cos_t = (1_f32 - sin2_t).sqrt();
+  let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+  let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+
+  0.5_f32 * (r_parl * r_parl + r_perp * r_perp)
+}
+
+/// Unpolarized Fresnel reflectance of a conductor with complex index of
+/// refraction `eta + i k`, for light arriving at `cos_i` to the normal.
+pub fn fresnel_conductor(cos_i: f32, eta: f32, k: f32) -> f32 {
+  let cos_i = cos_i.clamp(0_f32, 1_f32);
+  let cos2 = cos_i * cos_i;
+  let sin2 = 1_f32 - cos2;
+  let eta2 = eta * eta;
+  let k2 = k * k;
+
+  let t0 = eta2 - k2 - sin2;
+  let a2_plus_b2 = (t0 * t0 + 4_f32 * eta2 * k2).sqrt();
+  let t1 = a2_plus_b2 + cos2;
+  let a = (0.5_f32 * (a2_plus_b2 + t0)).max(0_f32).sqrt();
+  let t2 = 2_f32 * cos_i * a;
+  let rs = (t1 - t2) / (t1 + t2);
+
+  let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+  let t4 = t2 * sin2;
+  let rp = rs * (t3 - t4) / (t3 + t4);
+
+  0.5_f32 * (rp + rs)
+}
+
+/// Per channel conductor Fresnel, `eta` and `k` given for red, green and
+/// blue.
+pub fn fresnel_conductor_rgb(cos_i: f32, eta: Vec3, k: Vec3) -> Vec3 {
+  Vec3::new(
+    fresnel_conductor(cos_i, eta.x, k.x),
+    fresnel_conductor(cos_i, eta.y, k.y),
+    fresnel_conductor(cos_i, eta.z, k.z),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_dielectric_fresnel() {
+    assert!((fresnel_dielectric(1_f32, 1.5_f32) - 0.04_f32).abs() < 1e-4_f32);
+    assert!((fresnel_dielectric(0_f32, 1.5_f32) - 1_f32).abs() < 1e-4_f32);
+
+    //
+    // Past the critical angle (about 41.8 degrees for glass) light leaving
+    // the glass is reflected entirely.
+    let cos_45 = std::f32::consts::FRAC_1_SQRT_2;
+    assert_eq!(fresnel_dielectric(cos_45, 1_f32 / 1.5_f32), 1_f32);
+  }
+
+  #[test]
+  fn test_conductor_fresnel_limits() {
+    //
+    // With k = 0 normal incidence reduces to the dielectric ((n-1)/(n+1))^2.
+    let r = fresnel_conductor(1_f32, 1.5_f32, 0_f32);
+    assert!((r - 0.04_f32).abs() < 1e-4_f32);
+
+    //
+    // Everything is reflected at grazing angles.
+    let r = fresnel_conductor(0_f32, 0.2_f32, 3.9_f32);
+    assert!((r - 1_f32).abs() < 1e-4_f32);
+  }
+}
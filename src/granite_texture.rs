@@ -0,0 +1,93 @@
+use super::color_ramp::ColorRamp;
+use super::perlin::PerlinNoise;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::Vec3;
+
+/// Speckled crystals of a few minerals, picked from a colour ramp by high
+/// frequency turbulence.
+pub struct GraniteTexture {
+  noise: PerlinNoise,
+  /// Crystals per world unit.
+  pub scale: f32,
+  pub ramp: ColorRamp,
+}
+
+impl GraniteTexture {
+  pub fn new(seed: u64, ramp: ColorRamp) -> GraniteTexture {
+    GraniteTexture {
+      noise: PerlinNoise::with_seed(seed),
+      scale: 20_f32,
+      ramp,
+    }
+  }
+
+  /// Black mica and white quartz in grey feldspar.
+  pub fn grey(seed: u64) -> GraniteTexture {
+    GraniteTexture::new(
+      seed,
+      ColorRamp::new(vec![
+        (0.1_f32, Vec3::same(0.05_f32)),
+        (0.15_f32, Vec3::same(0.45_f32)),
+        (0.35_f32, Vec3::new(0.6_f32, 0.6_f32, 0.62_f32)),
+        (0.45_f32, Vec3::same(0.9_f32)),
+      ]),
+    )
+  }
+
+  /// Pink feldspar with dark and white speckles.
+  pub fn pink(seed: u64) -> GraniteTexture {
+    GraniteTexture::new(
+      seed,
+      ColorRamp::new(vec![
+        (0.1_f32, Vec3::same(0.05_f32)),
+        (0.15_f32, Vec3::new(0.7_f32, 0.45_f32, 0.4_f32)),
+        (0.35_f32, Vec3::new(0.8_f32, 0.55_f32, 0.5_f32)),
+        (0.45_f32, Vec3::same(0.9_f32)),
+      ]),
+    )
+  }
+
+  pub fn with_scale(mut self, scale: f32) -> GraniteTexture {
+    self.scale = scale;
+    self
+  }
+}
+
+impl Texture for GraniteTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self
+      .ramp
+      .evaluate(self.noise.turbulence(self.scale * at.p, 6).min(1_f32))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::noise_texture::{check_preset, sample_points};
+
+  #[test]
+  fn test_granite_presets() {
+    let (lo, hi) = (Vec3::same(0.05_f32), Vec3::same(0.9_f32));
+    check_preset(GraniteTexture::grey, lo, hi);
+    check_preset(GraniteTexture::pink, lo, hi);
+  }
+
+  #[test]
+  fn test_grain_shrinks_with_scale() {
+    //
+    // Twice the scale packs the same crystals into half the space.
+    let fine = GraniteTexture::grey(3).with_scale(40_f32);
+    let coarse = GraniteTexture::grey(3);
+    let points = sample_points();
+    for &p in points.iter() {
+      assert_eq!(
+        fine.value(0_f32, 0_f32, 0.5_f32 * p),
+        coarse.value(0_f32, 0_f32, p)
+      );
+    }
+    assert!(points.iter().any(|&p| {
+      fine.value(0_f32, 0_f32, p) != coarse.value(0_f32, 0_f32, p)
+    }));
+  }
+}
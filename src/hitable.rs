@@ -1,16 +1,32 @@
 use super::aabb::Aabb;
 use super::material::Material;
+use super::onb::Onb;
 use super::ray::Ray;
 use super::vec3::Vec3;
 use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct HitRecord {
   pub t: f32,
   pub p: Vec3,
+  /// Shading normal, which materials light the surface with. Bump and
+  /// normal maps perturb it.
   pub normal: Vec3,
-  pub mtl: Arc<Material>,
+  /// Normal of the actual surface, which decides what is inside and
+  /// outside.
+  pub geometric_normal: Vec3,
+  pub mtl: Arc<dyn Material>,
   pub u: f32,
   pub v: f32,
+  /// Unit direction of increasing `u` in the tangent plane, which orients
+  /// anisotropic materials.
+  pub tangent: Vec3,
+  /// Change in position and normal with (u, v), used to work out texture
+  /// footprints. Zero when the surface doesn't provide them.
+  pub dpdu: Vec3,
+  pub dpdv: Vec3,
+  pub dndu: Vec3,
+  pub dndv: Vec3,
 }
 
 impl HitRecord {
@@ -18,7 +34,7 @@ impl HitRecord {
     t: f32,
     p: Vec3,
     normal: Vec3,
-    mtl: Arc<Material>,
+    mtl: Arc<dyn Material>,
     u: f32,
     v: f32,
   ) -> HitRecord {
@@ -26,11 +42,37 @@ impl HitRecord {
       t,
       p,
       normal,
+      geometric_normal: normal,
       mtl,
       u,
       v,
+      tangent: Onb::from_w(normal).u,
+      dpdu: Vec3::same(0_f32),
+      dpdv: Vec3::same(0_f32),
+      dndu: Vec3::same(0_f32),
+      dndv: Vec3::same(0_f32),
     }
   }
+
+  /// Replaces the arbitrary default tangent with the surface's own.
+  pub fn with_tangent(mut self, tangent: Vec3) -> HitRecord {
+    self.tangent = tangent;
+    self
+  }
+
+  pub fn with_derivatives(
+    mut self,
+    dpdu: Vec3,
+    dpdv: Vec3,
+    dndu: Vec3,
+    dndv: Vec3,
+  ) -> HitRecord {
+    self.dpdu = dpdu;
+    self.dpdv = dpdv;
+    self.dndu = dndu;
+    self.dndv = dndv;
+    self
+  }
 }
 
 pub trait Hitable: Send + Sync {
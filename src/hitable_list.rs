@@ -4,7 +4,7 @@ use super::ray::Ray;
 use std::sync::Arc;
 
 pub struct HitableList {
-  objects: Vec<Arc<Hitable>>,
+  objects: Vec<Arc<dyn Hitable>>,
 }
 
 impl HitableList {
@@ -14,7 +14,7 @@ impl HitableList {
     }
   }
 
-  pub fn add_object(&mut self, obj: Arc<Hitable>) {
+  pub fn add_object(&mut self, obj: Arc<dyn Hitable>) {
     self.objects.push(obj);
   }
 
@@ -22,11 +22,11 @@ impl HitableList {
     self.objects.len()
   }
 
-  pub fn as_slice(&self) -> &[Arc<Hitable>] {
+  pub fn as_slice(&self) -> &[Arc<dyn Hitable>] {
     self.objects.as_slice()
   }
 
-  pub fn as_mut_slice(&mut self) -> &mut [Arc<Hitable>] {
+  pub fn as_mut_slice(&mut self) -> &mut [Arc<dyn Hitable>] {
     self.objects.as_mut_slice()
   }
 }
@@ -47,7 +47,7 @@ impl Hitable for HitableList {
   }
 
   fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
-    if self.objects.len() == 0 {
+    if self.objects.is_empty() {
       return None;
     }
 
@@ -0,0 +1,426 @@
+use super::texture::{Texture, TexturePoint};
+use super::vec3::Vec3;
+use std::fs::File;
+use std::io;
+
+/// Longest an elliptical footprint may get relative to its width before
+/// it is widened, bounding the texels read per lookup.
+const MAX_ANISOTROPY: f32 = 8_f32;
+
+/// How an image is filtered over the footprint of a pixel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TextureFilter {
+  /// Bilinear lookup in the full resolution image, ignoring the
+  /// footprint. Aliases in the distance.
+  Bilinear,
+  /// Bilinear lookups in the two MIP levels nearest the width of the
+  /// footprint, blended. Blurs surfaces seen at grazing angles.
+  Trilinear,
+  /// Gaussian weighted average over the elliptical footprint (Heckbert,
+  /// "Fundamentals of Texture Mapping and Image Warping", 1989). Stays
+  /// sharp at grazing angles.
+  Ewa,
+}
+
+/// One level of the MIP pyramid.
+struct Level {
+  width: u32,
+  height: u32,
+  pixels: Vec<Vec3>,
+}
+
+impl Level {
+  fn texel(&self, x: i64, y: i64) -> Vec3 {
+    let x = x.rem_euclid(self.width as i64) as usize;
+    let y = y.rem_euclid(self.height as i64) as usize;
+    self.pixels[y * self.width as usize + x]
+  }
+
+  fn bilinear(&self, u: f32, v: f32) -> Vec3 {
+    let x = u * self.width as f32 - 0.5_f32;
+    let y = (1_f32 - v) * self.height as f32 - 0.5_f32;
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x0, y0) = (x0 as i64, y0 as i64);
+
+    let top = (1_f32 - fx) * self.texel(x0, y0) + fx * self.texel(x0 + 1, y0);
+    let bottom =
+      (1_f32 - fx) * self.texel(x0, y0 + 1) + fx * self.texel(x0 + 1, y0 + 1);
+
+    (1_f32 - fy) * top + fy * bottom
+  }
+
+  /// Half the size, rounding up, each texel the average of the ones it
+  /// covers.
+  fn downsample(&self) -> Level {
+    let width = self.width.div_ceil(2);
+    let height = self.height.div_ceil(2);
+
+    //
+    // An odd edge has no partner texel past it; the last one stands in
+    // rather than wrapping to the other side.
+    let at = |x: u32, y: u32| {
+      self.texel(x.min(self.width - 1) as i64, y.min(self.height - 1) as i64)
+    };
+    let pixels = (0..width * height)
+      .map(|i| {
+        let (x, y) = (2 * (i % width), 2 * (i / width));
+        0.25_f32 * (at(x, y) + at(x + 1, y) + at(x, y + 1) + at(x + 1, y + 1))
+      })
+      .collect();
+
+    Level {
+      width,
+      height,
+      pixels,
+    }
+  }
+
+  /// Gaussian weighted sum of the texels inside the ellipse with axes
+  /// `a` and `b`, given in texels of this level, centred on (u, v).
+  fn ewa(&self, u: f32, v: f32, a: (f32, f32), b: (f32, f32)) -> Vec3 {
+    let x = u * self.width as f32 - 0.5_f32;
+    let y = (1_f32 - v) * self.height as f32 - 0.5_f32;
+
+    //
+    // The ellipse as the implicit quadratic A dx^2 + B dx dy + C dy^2 = 1.
+    // The ones widen it to at least a texel so it can't fall between them.
+    let mut qa = a.1 * a.1 + b.1 * b.1 + 1_f32;
+    let mut qb = -2_f32 * (a.0 * a.1 + b.0 * b.1);
+    let mut qc = a.0 * a.0 + b.0 * b.0 + 1_f32;
+    let inv_f = 1_f32 / (qa * qc - 0.25_f32 * qb * qb);
+    qa *= inv_f;
+    qb *= inv_f;
+    qc *= inv_f;
+
+    let det = 4_f32 * qa * qc - qb * qb;
+    let half_width = 2_f32 * (qc / det).sqrt();
+    let half_height = 2_f32 * (qa / det).sqrt();
+
+    let mut sum = Vec3::same(0_f32);
+    let mut weights = 0_f32;
+    for ty in (y - half_height).ceil() as i64..=(y + half_height) as i64 {
+      let dy = ty as f32 - y;
+      for tx in (x - half_width).ceil() as i64..=(x + half_width) as i64 {
+        let dx = tx as f32 - x;
+        let r2 = qa * dx * dx + qb * dx * dy + qc * dy * dy;
+        if r2 < 1_f32 {
+          let w = (-2_f32 * r2).exp() - (-2_f32).exp();
+          sum += w * self.texel(tx, ty);
+          weights += w;
+        }
+      }
+    }
+
+    if weights > 0_f32 {
+      sum / weights
+    } else {
+      self.bilinear(u, v)
+    }
+  }
+}
+
+/// A bitmap looked up by (u, v), repeating outside [0, 1]. v = 0 is the
+/// bottom row of the image. A MIP pyramid of ever smaller copies is built
+/// up front so lookups can average over a pixel's footprint cheaply.
+pub struct ImageTexture {
+  levels: Vec<Level>,
+  filter: TextureFilter,
+}
+
+impl ImageTexture {
+  /// Texels in rows from the top, each channel in [0, 1]. The image must
+  /// not be empty.
+  pub fn from_pixels(
+    width: u32,
+    height: u32,
+    pixels: Vec<Vec3>,
+  ) -> ImageTexture {
+    assert!(width > 0 && height > 0, "empty image");
+    assert_eq!(pixels.len(), (width * height) as usize);
+
+    let mut levels = vec![Level {
+      width,
+      height,
+      pixels,
+    }];
+    loop {
+      let last = &levels[levels.len() - 1];
+      if last.width == 1 && last.height == 1 {
+        break;
+      }
+      let next = last.downsample();
+      levels.push(next);
+    }
+
+    ImageTexture {
+      levels,
+      filter: TextureFilter::Trilinear,
+    }
+  }
+
+  /// Loads a PNG, keeping the stored values. Colour images saved with the
+  /// usual gamma want `decode_gamma` as well; data such as normal maps do
+  /// not.
+  pub fn open(path: &str) -> io::Result<ImageTexture> {
+    use png::HasParameters;
+
+    //
+    // Palettes and low bit depths are expanded, and 16 bit channels cut
+    // to their high byte, so every channel arrives as one byte.
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let (info, mut reader) = decoder.read_info()?;
+    if info.bit_depth != png::BitDepth::Eight {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "unsupported PNG bit depth",
+      ));
+    }
+    let mut buf = vec![0_u8; info.buffer_size()];
+    reader.next_frame(&mut buf)?;
+
+    let channels = match info.color_type {
+      png::ColorType::Grayscale => 1,
+      png::ColorType::GrayscaleAlpha => 2,
+      png::ColorType::RGB => 3,
+      png::ColorType::RGBA => 4,
+      png::ColorType::Indexed => {
+        return Err(io::Error::new(
+          io::ErrorKind::InvalidData,
+          "indexed PNG was not expanded",
+        ));
+      }
+    };
+
+    let texel = |i: usize| buf[i] as f32 / 255_f32;
+    let pixels = (0..(info.width * info.height) as usize)
+      .map(|i| {
+        let o = i * channels;
+        if channels < 3 {
+          Vec3::same(texel(o))
+        } else {
+          Vec3::new(texel(o), texel(o + 1), texel(o + 2))
+        }
+      })
+      .collect();
+
+    Ok(ImageTexture::from_pixels(info.width, info.height, pixels))
+  }
+
+  /// Converts gamma encoded colours to linear, the inverse of the square
+  /// root applied when saving renders. The pyramid is rebuilt so it
+  /// averages linear values.
+  pub fn decode_gamma(self) -> ImageTexture {
+    let filter = self.filter;
+    let base = self.levels.into_iter().next().unwrap();
+    let pixels = base.pixels.iter().map(|&p| p * p).collect();
+
+    ImageTexture::from_pixels(base.width, base.height, pixels)
+      .with_filter(filter)
+  }
+
+  pub fn with_filter(mut self, filter: TextureFilter) -> ImageTexture {
+    self.filter = filter;
+    self
+  }
+
+  pub fn width(&self) -> u32 {
+    self.levels[0].width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.levels[0].height
+  }
+
+  /// Number of MIP levels, down to a single texel.
+  pub fn levels(&self) -> usize {
+    self.levels.len()
+  }
+
+  /// Texel at integer coordinates, wrapping around the edges. y = 0 is the
+  /// top row.
+  pub fn texel(&self, x: i64, y: i64) -> Vec3 {
+    self.levels[0].texel(x, y)
+  }
+
+  /// Bilinear lookup at (u, v).
+  pub fn bilinear(&self, u: f32, v: f32) -> Vec3 {
+    self.levels[0].bilinear(u, v)
+  }
+
+  /// Bilinear lookups at a fractional MIP level, blended.
+  pub fn trilinear(&self, u: f32, v: f32, level: f32) -> Vec3 {
+    let last = (self.levels.len() - 1) as f32;
+    let level = level.clamp(0_f32, last);
+    let i = level.floor();
+    if i >= last {
+      return self.levels[i as usize].bilinear(u, v);
+    }
+
+    let f = level - i;
+    let i = i as usize;
+    (1_f32 - f) * self.levels[i].bilinear(u, v)
+      + f * self.levels[i + 1].bilinear(u, v)
+  }
+
+  /// Elliptically weighted average over the footprint with axes `a` and
+  /// `b`, in (u, v).
+  pub fn ewa(&self, u: f32, v: f32, a: (f32, f32), b: (f32, f32)) -> Vec3 {
+    //
+    // Axes in texels of the full image, y pointing down the rows.
+    let to_texels =
+      |d: (f32, f32)| (d.0 * self.width() as f32, -d.1 * self.height() as f32);
+    let length = |d: (f32, f32)| (d.0 * d.0 + d.1 * d.1).sqrt();
+    let (mut major, mut minor) = (to_texels(a), to_texels(b));
+    if length(major) < length(minor) {
+      std::mem::swap(&mut major, &mut minor);
+    }
+
+    //
+    // Widen very thin ellipses, trading sharpness for a bounded cost.
+    let (major_length, mut minor_length) = (length(major), length(minor));
+    if minor_length * MAX_ANISOTROPY < major_length {
+      let scale = major_length / (minor_length * MAX_ANISOTROPY).max(1e-8);
+      if minor_length > 0_f32 {
+        minor = (minor.0 * scale, minor.1 * scale);
+      } else {
+        let n = (-major.1 / major_length, major.0 / major_length);
+        let w = major_length / MAX_ANISOTROPY;
+        minor = (n.0 * w, n.1 * w);
+      }
+      minor_length = length(minor);
+    }
+    if minor_length == 0_f32 {
+      return self.bilinear(u, v);
+    }
+
+    //
+    // Pick the level where the short axis spans about a texel, so the
+    // long one spans at most MAX_ANISOTROPY of them.
+    let last = self.levels.len() - 1;
+    let level = minor_length.log2().clamp(0_f32, last as f32);
+    let i = level.floor() as usize;
+    let at = |i: usize| {
+      let level = &self.levels[i];
+      let scale_x = level.width as f32 / self.width() as f32;
+      let scale_y = level.height as f32 / self.height() as f32;
+      level.ewa(
+        u,
+        v,
+        (major.0 * scale_x, major.1 * scale_y),
+        (minor.0 * scale_x, minor.1 * scale_y),
+      )
+    };
+
+    if i >= last {
+      return at(last);
+    }
+    let f = level - i as f32;
+    (1_f32 - f) * at(i) + f * at(i + 1)
+  }
+}
+
+impl Texture for ImageTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    let (u, v) = (at.u, at.v);
+    let f = match at.footprint {
+      Some(f) => f,
+      None => return self.bilinear(u, v),
+    };
+
+    match self.filter {
+      TextureFilter::Bilinear => self.bilinear(u, v),
+      TextureFilter::Trilinear => {
+        //
+        // The level where a texel is as wide as the footprint's widest
+        // extent.
+        let (w, h) = (self.width() as f32, self.height() as f32);
+        let texels = |du: f32, dv: f32| (du * w).hypot(dv * h);
+        let width = texels(f.dudx, f.dvdx).max(texels(f.dudy, f.dvdy));
+        self.trilinear(u, v, width.max(1e-8_f32).log2())
+      }
+      TextureFilter::Ewa => self.ewa(u, v, (f.dudx, f.dvdx), (f.dudy, f.dvdy)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::ray_differential::Footprint;
+
+  #[test]
+  fn test_bilinear_lookup() {
+    let black = Vec3::same(0_f32);
+    let white = Vec3::same(1_f32);
+    let image =
+      ImageTexture::from_pixels(2, 2, vec![white, black, black, black]);
+
+    //
+    // The top left texel is at v = 0.75, and lookups wrap around.
+    assert_eq!(image.bilinear(0.25_f32, 0.75_f32), white);
+    assert_eq!(image.bilinear(1.25_f32, -0.25_f32), white);
+    assert_eq!(image.bilinear(0.25_f32, 0.25_f32), black);
+    assert!((image.bilinear(0.5_f32, 0.75_f32).x - 0.5_f32).abs() < 1e-6_f32);
+  }
+
+  #[test]
+  fn test_filtering_a_checker_from_afar_gives_grey() {
+    let size = 64;
+    let checker = |filter: TextureFilter| {
+      let pixels = (0..size * size)
+        .map(|i| Vec3::same(((i % size + i / size) % 2) as f32))
+        .collect();
+      ImageTexture::from_pixels(size, size, pixels).with_filter(filter)
+    };
+    assert_eq!(checker(TextureFilter::Bilinear).levels(), 7);
+
+    //
+    // A footprint a quarter of the image wide, and one as long but thin.
+    let wide = Footprint {
+      dpdx: Vec3::same(0_f32),
+      dpdy: Vec3::same(0_f32),
+      dudx: 0.25_f32,
+      dvdx: 0_f32,
+      dudy: 0_f32,
+      dvdy: 0.25_f32,
+    };
+    let thin = Footprint {
+      dvdy: 0.01_f32,
+      ..wide
+    };
+    for &filter in [TextureFilter::Trilinear, TextureFilter::Ewa].iter() {
+      let image = checker(filter);
+      for &f in [wide, thin].iter() {
+        let c = image.lookup(&TexturePoint {
+          footprint: Some(f),
+          ..TexturePoint::new(0.3_f32, 0.6_f32, Vec3::same(0_f32))
+        });
+        assert!((c.x - 0.5_f32).abs() < 0.05_f32, "{:?} {:?}", filter, c);
+      }
+    }
+  }
+
+  #[test]
+  fn test_open_sixteen_bit_png() {
+    use png::HasParameters;
+
+    let path = std::env::temp_dir()
+      .join(format!("raytracer-test-{}-16.png", std::process::id()));
+    {
+      let mut encoder = png::Encoder::new(File::create(&path).unwrap(), 2, 1);
+      encoder
+        .set(png::ColorType::Grayscale)
+        .set(png::BitDepth::Sixteen);
+      let mut writer = encoder.write_header().unwrap();
+      writer.write_image_data(&[0xff, 0xff, 0x80, 0x00]).unwrap();
+    }
+    let image = ImageTexture::open(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!((image.width(), image.height()), (2, 1));
+    assert_eq!(image.levels[0].pixels[0], Vec3::same(1_f32));
+    assert_eq!(image.levels[0].pixels[1], Vec3::same(128_f32 / 255_f32));
+  }
+}
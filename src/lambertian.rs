@@ -6,11 +6,11 @@ use super::vec3::{random_in_unit_sphere, Vec3};
 use std::sync::Arc;
 
 pub struct Lambertian {
-  pub albedo: Arc<Texture>,
+  pub albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
-  pub fn new(albedo: Arc<Texture>) -> Lambertian {
+  pub fn new(albedo: Arc<dyn Texture>) -> Lambertian {
     Lambertian { albedo }
   }
 }
@@ -19,7 +19,7 @@ impl Material for Lambertian {
   fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
     let target = h.p + h.normal + random_in_unit_sphere();
     let scattered = Ray::new(h.p, target - h.p, r.time);
-    let attenuation = self.albedo.value(0_f32, 0_f32, h.p);
+    let attenuation = self.albedo.value_at(r, h);
 
     Some((attenuation, scattered))
   }
@@ -1,61 +1,134 @@
 #![allow(dead_code)]
 
+extern crate ctrlc;
 extern crate png;
 extern crate rand;
 extern crate rgb;
 
-use std::sync::mpsc;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
-use std::thread;
 
 use rgb::RGB8;
 
 mod aabb;
+mod alpha_mask;
+mod bump_map;
 mod bvh_node;
 mod camera;
 mod checker_texture;
+mod checkpoint;
+mod cloth;
+mod coated;
+mod color_ramp;
+mod conductor;
 mod constant_texture;
 mod dielectric;
 mod diffuse_light;
+mod film;
+mod filter;
 mod flip_normals;
+mod fractal_noise;
+mod fractal_texture;
+mod fresnel;
+mod granite_texture;
 mod hitable;
 mod hitable_list;
+mod image_texture;
 mod lambertian;
+mod marble_texture;
 mod material;
 mod metal;
+mod microfacet;
+mod mix_material;
 mod moving_sphere;
 mod noise_texture;
+mod normal_map;
+mod onb;
+mod options;
+mod oren_nayar;
 mod perlin;
+mod principled;
+mod progress;
 mod ray;
+mod ray_differential;
+mod renderer;
+mod rough_dielectric;
+mod sampler;
 mod sphere;
+mod subsurface;
 mod texture;
+mod texture_math;
+mod texture_transform;
+mod thin_film;
+mod tile_scheduler;
 mod timer;
+mod triplanar;
+mod uv_checker_texture;
 mod vec3;
 mod window;
+mod wood_texture;
+mod worley;
 mod xy_rect;
 mod xz_rect;
 mod yz_rect;
 
+use alpha_mask::AlphaMask;
+use bump_map::BumpMap;
 use bvh_node::BvhNode;
 use camera::{Camera, CameraParameters};
 use checker_texture::CheckerTexture;
+use checkpoint::CheckpointHeader;
+use cloth::Cloth;
+use coated::Coated;
+use color_ramp::ColorRamp;
+use conductor::Conductor;
 use constant_texture::ConstantTexture;
 use dielectric::Dielectric;
 use diffuse_light::DiffuseLight;
+use film::Film;
 use flip_normals::FlipNormals;
+use fractal_noise::FractalNoise;
+use fractal_texture::{
+  DomainWarpTexture, FbmTexture, RidgedTexture, TurbulenceTexture,
+};
+use granite_texture::GraniteTexture;
 use hitable::*;
 use hitable_list::HitableList;
+use image_texture::{ImageTexture, TextureFilter};
 use lambertian::Lambertian;
+use marble_texture::MarbleTexture;
 use material::Material;
 use metal::Metal;
-use moving_sphere::MovingSphere;
+use mix_material::MixMaterial;
 use noise_texture::NoiseTexture;
+use normal_map::NormalMap;
+use options::RenderOptions;
+use oren_nayar::OrenNayar;
+use principled::Principled;
+use progress::ProgressBar;
 use rand::prelude::*;
-use ray::Ray;
+use renderer::{
+  render_progressive, ProgressiveSettings, Renderer, TileSchedule,
+};
+use rough_dielectric::RoughDielectric;
+use sampler::Sampler;
 use sphere::Sphere;
+use subsurface::Subsurface;
+use texture::Texture;
+use texture_math::{
+  AddTexture, ClampTexture, InvertTexture, MixTexture, MultiplyTexture,
+  RampTexture, ScaleTexture,
+};
+use texture_transform::{PositionTransform, UvTransform};
+use thin_film::ThinFilm;
 use timer::BasicTimer;
-use vec3::{to_rgb8, Vec3};
-use window::Window;
+use triplanar::Triplanar;
+use uv_checker_texture::{
+  GridTexture, SolidCheckerTexture, UvCheckerTexture,
+};
+use vec3::Vec3;
+use wood_texture::WoodTexture;
+use worley::{DistanceMetric, WorleyFeature, WorleyNoise, WorleyTexture};
 use xy_rect::XYRect;
 use xz_rect::XZRect;
 use yz_rect::YZRect;
@@ -81,38 +154,20 @@ fn write_image(
     std::slice::from_raw_parts(pixels.as_ptr() as *const u8, pixels.len() * 3)
   };
 
-  png_writer.write_image_data(&img_data)?;
+  png_writer.write_image_data(img_data)?;
 
   Ok(())
 }
 
-fn color(r: &Ray, world: &Arc<Hitable>, depth: i32) -> Vec3 {
-  if let Some(hit) = world.hit(r, 0.001f32, std::f32::MAX) {
-    let emitted = hit.mtl.emitted(hit.u, hit.v, hit.p);
-    if depth < 50 {
-      if let Some((attn, scattered)) = hit.mtl.scatter(r, &hit) {
-        return emitted + attn * color(&scattered, world, depth + 1);
-      }
-    }
-
-    return emitted;
-  }
-
-  Vec3::same(0_f32)
-
-  // let unit_direction = vec3::unit_vector(r.direction);
-  // let t = 0.5f32 * (unit_direction.y + 1f32);
-  // (1f32 - t) * Vec3::new(1f32, 1f32, 1f32) + t * Vec3::new(0.5f32, 0.7f32, 1f32)
-}
-
 struct WorldBuilder {}
 
 impl WorldBuilder {
-  fn default_world() -> HitableList {
+  fn default_world(seed: u64) -> HitableList {
     let mut world = HitableList::new();
 
-    let noise_tex =
-      Arc::new(Lambertian::new(Arc::new(NoiseTexture::new(8_f32))));
+    let noise_tex = Arc::new(Lambertian::new(Arc::new(
+      NoiseTexture::new(8_f32).with_seed(seed),
+    )));
 
     world.add_object(Arc::new(Sphere::new(
       Vec3::new(0f32, -1000f32, 0f32),
@@ -120,7 +175,7 @@ impl WorldBuilder {
       noise_tex.clone(),
     )));
 
-    let mut rng = thread_rng();
+    let mut rng = StdRng::seed_from_u64(seed);
 
     for a in -11..11 {
       for b in -11..11 {
@@ -133,7 +188,7 @@ impl WorldBuilder {
         );
 
         if (center - Vec3::new(4f32, 0.2f32, 0f32)).length() > 0.9f32 {
-          let mtl: Arc<Material> = if choose_mat < 0.8f32 {
+          let mtl: Arc<dyn Material> = if choose_mat < 0.8f32 {
             //
             // diffuse
             let texture = Arc::new(ConstantTexture::new(Vec3::new(
@@ -188,21 +243,23 @@ impl WorldBuilder {
     world
   }
 
-  pub fn random_world_bvh() -> (Arc<Hitable>, CameraParameters) {
-    let mut world = WorldBuilder::default_world();
+  pub fn random_world_bvh(
+    seed: u64,
+  ) -> (Arc<dyn Hitable>, CameraParameters) {
+    let mut world = WorldBuilder::default_world(seed);
     (
       BvhNode::new(world.as_mut_slice(), 0_f32, 1_f32),
       WorldBuilder::default_camera(),
     )
   }
 
-  fn random_world() -> (Arc<Hitable>, CameraParameters) {
-    let world = WorldBuilder::default_world();
+  fn random_world(seed: u64) -> (Arc<dyn Hitable>, CameraParameters) {
+    let world = WorldBuilder::default_world(seed);
 
     (Arc::new(world), WorldBuilder::default_camera())
   }
 
-  fn two_perlin_spheres() -> (Arc<Hitable>, CameraParameters) {
+  fn two_perlin_spheres() -> (Arc<dyn Hitable>, CameraParameters) {
     let perlin_tex =
       Arc::new(Lambertian::new(Arc::new(NoiseTexture::new(4_f32))));
     let mut world = HitableList::new();
@@ -222,7 +279,7 @@ impl WorldBuilder {
     (Arc::new(world), WorldBuilder::default_camera())
   }
 
-  fn two_spheres() -> (Arc<Hitable>, CameraParameters) {
+  fn two_spheres() -> (Arc<dyn Hitable>, CameraParameters) {
     let odd =
       Arc::new(ConstantTexture::new(Vec3::new(0.2_f32, 0.3_f32, 0.1_f32)));
     let even =
@@ -248,7 +305,7 @@ impl WorldBuilder {
     (Arc::new(world), WorldBuilder::default_camera())
   }
 
-  fn simple_light() -> (Arc<Hitable>, CameraParameters) {
+  fn simple_light() -> (Arc<dyn Hitable>, CameraParameters) {
     let perlin_tex =
       Arc::new(Lambertian::new(Arc::new(NoiseTexture::new(4_f32))));
     let light_mtl = Arc::new(DiffuseLight::new(Arc::new(
@@ -283,7 +340,7 @@ impl WorldBuilder {
     (Arc::new(world), WorldBuilder::default_camera())
   }
 
-  fn cornell_box() -> (Arc<Hitable>, CameraParameters) {
+  fn cornell_box() -> (Arc<dyn Hitable>, CameraParameters) {
     let red = Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
       Vec3::new(0.65_f32, 0.05_f32, 0.05_f32),
     ))));
@@ -296,9 +353,11 @@ impl WorldBuilder {
       Vec3::new(0.12_f32, 0.45_f32, 0.15_f32),
     ))));
 
-    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
-      Vec3::same(15_f32),
-    ))));
+    let light = Arc::new(
+      DiffuseLight::new(Arc::new(ConstantTexture::same(1_f32)))
+        .with_scale(15_f32)
+        .one_sided(),
+    );
 
     let mut world = HitableList::new();
 
@@ -310,10 +369,10 @@ impl WorldBuilder {
       0_f32, 555_f32, 0_f32, 555_f32, 0_f32, red,
     )));
 
-    world.add_object(Arc::new(XZRect::new(
+    world.add_object(Arc::new(FlipNormals::new(Arc::new(XZRect::new(
       //213_f32, 343_f32, 227_f32, 332_f32, 554_f32, light,
       123_f32, 423_f32, 147_f32, 412_f32, 554_f32, light,
-    )));
+    )))));
 
     world.add_object(Arc::new(FlipNormals::new(Arc::new(XZRect::new(
       0_f32,
@@ -342,56 +401,998 @@ impl WorldBuilder {
       white.clone(),
     )))));
 
-    let cam_params = {
-      let mut cp = CameraParameters::default();
-      cp.lookfrom = Vec3::new(278_f32, 278f32, -800_f32);
-      cp.lookat = Vec3::new(278_f32, 278_f32, 0_f32);
-      cp.world_up = Vec3::new(0_f32, 1_f32, 0_f32);
-      cp.focus_dist = 10_f32;
-      cp.aperture = 0_f32;
-      cp.field_of_view = 40_f32;
-      cp.time0 = 0_f32;
-      cp.time1 = 1_f32;
-
-      cp
+    let cam_params = CameraParameters {
+      lookfrom: Vec3::new(278_f32, 278f32, -800_f32),
+      lookat: Vec3::new(278_f32, 278_f32, 0_f32),
+      world_up: Vec3::new(0_f32, 1_f32, 0_f32),
+      focus_dist: 10_f32,
+      aperture: 0_f32,
+      field_of_view: 40_f32,
+      time0: 0_f32,
+      time1: 1_f32,
     };
 
     (Arc::new(world), cam_params)
   }
 
-  fn default_camera() -> CameraParameters {
-    let mut defparams = CameraParameters::default();
+  /// Centres of `n` unit spheres in a row, for `object_lineup`.
+  fn lineup_centers(n: usize) -> Vec<Vec3> {
+    let spacing = 2.5_f32;
+    let x0 = -0.5_f32 * spacing * (n as f32 - 1_f32);
+    (0..n)
+      .map(|i| Vec3::new(x0 + i as f32 * spacing, 1_f32, 0_f32))
+      .collect()
+  }
+
+  /// A row of unit spheres, one per material, on a grey floor under a
+  /// large area light.
+  fn material_lineup(
+    materials: Vec<Arc<dyn Material>>,
+  ) -> (Arc<dyn Hitable>, CameraParameters) {
+    let centers = WorldBuilder::lineup_centers(materials.len());
+    WorldBuilder::object_lineup(
+      centers
+        .into_iter()
+        .zip(materials)
+        .map(|(center, mtl)| -> Arc<dyn Hitable> {
+          Arc::new(Sphere::new(center, 1_f32, mtl))
+        })
+        .collect(),
+    )
+  }
+
+  /// The objects on the stage of `material_lineup`.
+  fn object_lineup(
+    objects: Vec<Arc<dyn Hitable>>,
+  ) -> (Arc<dyn Hitable>, CameraParameters) {
+    let floor = Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(
+      Vec3::same(0.5_f32),
+    ))));
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::new(
+      Vec3::same(3_f32),
+    ))));
+
+    let mut world = HitableList::new();
+    world.add_object(Arc::new(Sphere::new(
+      Vec3::new(0_f32, -1000_f32, 0_f32),
+      1000_f32,
+      floor,
+    )));
+    world.add_object(Arc::new(FlipNormals::new(Arc::new(XZRect::new(
+      -8_f32, 8_f32, -4_f32, 6_f32, 8_f32, light,
+    )))));
+
+    for obj in objects {
+      world.add_object(obj);
+    }
+
+    let cam_params = CameraParameters {
+      lookfrom: Vec3::new(0_f32, 3_f32, 14_f32),
+      lookat: Vec3::new(0_f32, 1_f32, 0_f32),
+      world_up: Vec3::new(0_f32, 1_f32, 0_f32),
+      focus_dist: 14_f32,
+      aperture: 0_f32,
+      field_of_view: 30_f32,
+      time0: 0_f32,
+      time1: 1_f32,
+    };
+
+    (Arc::new(world), cam_params)
+  }
+
+  fn metals() -> (Arc<dyn Hitable>, CameraParameters) {
+    WorldBuilder::material_lineup(vec![
+      Arc::new(Conductor::gold(0.05_f32)),
+      Arc::new(Conductor::copper(0.25_f32)),
+      Arc::new(Conductor::aluminium(0.4_f32)),
+      Arc::new(Conductor::silver(0.15_f32)),
+    ])
+  }
+
+  fn frosted_glass() -> (Arc<dyn Hitable>, CameraParameters) {
+    WorldBuilder::material_lineup(vec![
+      Arc::new(Dielectric::new(1.5_f32)),
+      Arc::new(RoughDielectric::with_roughness(1.5_f32, 0.1_f32)),
+      Arc::new(RoughDielectric::with_roughness(1.5_f32, 0.35_f32)),
+      Arc::new(RoughDielectric::new(
+        1.5_f32,
+        Arc::new(NoiseTexture::new(4_f32)),
+      )),
+    ])
+  }
+
+  fn tinted_glass() -> (Arc<dyn Hitable>, CameraParameters) {
+    WorldBuilder::material_lineup(vec![
+      Arc::new(Dielectric::tinted(
+        1.5_f32,
+        Vec3::new(0.9_f32, 0.3_f32, 0.2_f32),
+        1_f32,
+      )),
+      Arc::new(Dielectric::tinted(
+        1.33_f32,
+        Vec3::new(0.5_f32, 0.8_f32, 0.95_f32),
+        2_f32,
+      )),
+      Arc::new(Dielectric::tinted(
+        2.42_f32,
+        Vec3::new(0.3_f32, 0.9_f32, 0.4_f32),
+        1_f32,
+      )),
+      Arc::new(
+        RoughDielectric::with_roughness(1.5_f32, 0.2_f32).tinted(
+          Vec3::new(0.95_f32, 0.75_f32, 0.2_f32),
+          1_f32,
+        ),
+      ),
+    ])
+  }
+
+  fn principled() -> (Arc<dyn Hitable>, CameraParameters) {
+    let constant = |v: f32| -> Arc<dyn Texture> {
+      Arc::new(ConstantTexture::same(v))
+    };
+    let color = |r: f32, g: f32, b: f32| -> Arc<dyn Texture> {
+      Arc::new(ConstantTexture::new(Vec3::new(r, g, b)))
+    };
+
+    WorldBuilder::material_lineup(vec![
+      Arc::new(
+        Principled::new(color(0.8_f32, 0.1_f32, 0.1_f32))
+          .with_roughness(Arc::new(NoiseTexture::new(4_f32))),
+      ),
+      Arc::new(
+        Principled::new(color(0.95_f32, 0.64_f32, 0.54_f32))
+          .with_metallic(Arc::new(CheckerTexture::new(
+            constant(0_f32),
+            constant(1_f32),
+          )))
+          .with_roughness(constant(0.3_f32)),
+      ),
+      Arc::new(
+        Principled::new(color(0.05_f32, 0.15_f32, 0.5_f32))
+          .with_metallic(constant(0.6_f32))
+          .with_roughness(constant(0.5_f32))
+          .with_clearcoat(constant(1_f32)),
+      ),
+      Arc::new(
+        Principled::new(color(0.7_f32, 0.9_f32, 0.8_f32))
+          .with_transmission(constant(1_f32))
+          .with_roughness(constant(0.05_f32)),
+      ),
+    ])
+  }
 
-    defparams.lookfrom = Vec3::new(13f32, 2f32, 3f32);
-    defparams.lookat = Vec3::new(0f32, 0f32, 0f32);
-    defparams.world_up = Vec3::new(0_f32, 1_f32, 0_f32);
-    defparams.focus_dist = 10f32;
-    defparams.aperture = 0.1f32;
-    defparams.field_of_view = 20_f32;
-    defparams.time0 = 0_f32;
-    defparams.time1 = 1_f32;
+  fn layered() -> (Arc<dyn Hitable>, CameraParameters) {
+    let lambertian = |r: f32, g: f32, b: f32| -> Arc<dyn Material> {
+      Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(Vec3::new(
+        r, g, b,
+      )))))
+    };
 
-    defparams
+    WorldBuilder::material_lineup(vec![
+      Arc::new(MixMaterial::new(
+        lambertian(0.1_f32, 0.1_f32, 0.1_f32),
+        Arc::new(Conductor::gold(0.1_f32)),
+        Arc::new(CheckerTexture::new(
+          Arc::new(ConstantTexture::same(0_f32)),
+          Arc::new(ConstantTexture::same(1_f32)),
+        )),
+      )),
+      Arc::new(MixMaterial::new(
+        Arc::new(Conductor::copper(0.3_f32)),
+        lambertian(0.2_f32, 0.5_f32, 0.3_f32),
+        Arc::new(NoiseTexture::new(4_f32)),
+      )),
+      Arc::new(Coated::new(
+        lambertian(0.7_f32, 0.05_f32, 0.05_f32),
+        1.5_f32,
+        0_f32,
+      )),
+      Arc::new(
+        Coated::new(Arc::new(Conductor::aluminium(0.4_f32)), 1.5_f32, 0.05_f32)
+          .tinted(Vec3::new(0.3_f32, 0.5_f32, 0.9_f32)),
+      ),
+    ])
+  }
+
+  /// Seeded fBm clouds, ridged mountains, turbulent fire and a domain
+  /// warped swirl, each through its own colour ramp.
+  fn fractal_noise() -> (Arc<dyn Hitable>, CameraParameters) {
+    let lambertian = |tex: Arc<dyn Texture>| -> Arc<dyn Material> {
+      Arc::new(Lambertian::new(tex))
+    };
+    let ramp = |stops: &[(f32, f32, f32, f32)]| {
+      ColorRamp::new(
+        stops
+          .iter()
+          .map(|&(t, r, g, b)| (t, Vec3::new(r, g, b)))
+          .collect(),
+      )
+    };
+
+    WorldBuilder::material_lineup(vec![
+      lambertian(Arc::new(FbmTexture::new(
+        FractalNoise::new(1).with_frequency(2_f32),
+        ramp(&[
+          (0.35_f32, 0.2_f32, 0.35_f32, 0.7_f32),
+          (0.65_f32, 0.95_f32, 0.95_f32, 0.95_f32),
+        ]),
+      ))),
+      lambertian(Arc::new(RidgedTexture::new(
+        FractalNoise::new(2).with_frequency(1.5_f32).with_gain(0.6_f32),
+        ramp(&[
+          (0_f32, 0.15_f32, 0.25_f32, 0.1_f32),
+          (0.6_f32, 0.45_f32, 0.4_f32, 0.35_f32),
+          (0.9_f32, 0.95_f32, 0.95_f32, 1_f32),
+        ]),
+      ))),
+      lambertian(Arc::new(TurbulenceTexture::new(
+        FractalNoise::new(3).with_frequency(2_f32).with_octaves(8),
+        ramp(&[
+          (0_f32, 0.05_f32, 0_f32, 0_f32),
+          (0.2_f32, 0.8_f32, 0.1_f32, 0_f32),
+          (0.45_f32, 1_f32, 0.8_f32, 0.2_f32),
+        ]),
+      ))),
+      lambertian(Arc::new(DomainWarpTexture::new(
+        FractalNoise::new(4).with_frequency(1.5_f32).with_lacunarity(2.2_f32),
+        1.5_f32,
+        ramp(&[
+          (0.3_f32, 0.05_f32, 0.1_f32, 0.3_f32),
+          (0.5_f32, 0.2_f32, 0.6_f32, 0.6_f32),
+          (0.7_f32, 0.95_f32, 0.9_f32, 0.7_f32),
+        ]),
+      ))),
+    ])
+  }
+
+  /// Cells, cracked earth, diamond tiles and square stone blocks from
+  /// cellular noise with different features and metrics.
+  fn cellular() -> (Arc<dyn Hitable>, CameraParameters) {
+    let worley = |noise: WorleyNoise,
+                  feature: WorleyFeature,
+                  stops: Vec<(f32, Vec3)>|
+     -> Arc<dyn Material> {
+      Arc::new(Lambertian::new(Arc::new(WorleyTexture::new(
+        noise,
+        feature,
+        ColorRamp::new(stops),
+      ))))
+    };
+
+    WorldBuilder::material_lineup(vec![
+      worley(
+        WorleyNoise::new(1).with_frequency(3_f32),
+        WorleyFeature::F1,
+        vec![
+          (0_f32, Vec3::new(0.9_f32, 0.9_f32, 0.3_f32)),
+          (0.8_f32, Vec3::new(0.5_f32, 0.05_f32, 0.1_f32)),
+        ],
+      ),
+      worley(
+        WorleyNoise::new(2).with_frequency(2.5_f32),
+        WorleyFeature::F2MinusF1,
+        vec![
+          (0.02_f32, Vec3::new(0.1_f32, 0.05_f32, 0.02_f32)),
+          (0.06_f32, Vec3::new(0.6_f32, 0.4_f32, 0.25_f32)),
+          (0.6_f32, Vec3::new(0.75_f32, 0.55_f32, 0.35_f32)),
+        ],
+      ),
+      worley(
+        WorleyNoise::new(3)
+          .with_frequency(3_f32)
+          .with_metric(DistanceMetric::Manhattan)
+          .with_jitter(0.3_f32),
+        WorleyFeature::F2MinusF1,
+        vec![
+          (0.05_f32, Vec3::same(0.85_f32)),
+          (0.1_f32, Vec3::new(0.1_f32, 0.35_f32, 0.5_f32)),
+        ],
+      ),
+      worley(
+        WorleyNoise::new(4)
+          .with_frequency(2.5_f32)
+          .with_metric(DistanceMetric::Chebyshev)
+          .with_jitter(0.5_f32),
+        WorleyFeature::F2MinusF1,
+        vec![
+          (0.03_f32, Vec3::same(0.15_f32)),
+          (0.08_f32, Vec3::new(0.5_f32, 0.48_f32, 0.45_f32)),
+          (0.5_f32, Vec3::new(0.65_f32, 0.62_f32, 0.58_f32)),
+        ],
+      ),
+    ])
+  }
+
+  /// Oak and walnut turned from logs, white marble and grey granite.
+  fn wood_and_stone() -> (Arc<dyn Hitable>, CameraParameters) {
+    let c = WorldBuilder::lineup_centers(4);
+    let lambertian = |tex: Arc<dyn Texture>| -> Arc<dyn Material> {
+      Arc::new(Lambertian::new(tex))
+    };
+
+    //
+    // The logs run at a slant, just off the centre of the spheres, so the
+    // rings show as arcs rather than bullseyes.
+    WorldBuilder::material_lineup(vec![
+      lambertian(Arc::new(WoodTexture::oak(1).with_axis(
+        c[0] + Vec3::new(0.3_f32, 0_f32, -1.5_f32),
+        Vec3::new(0.2_f32, 1_f32, 0.4_f32),
+      ))),
+      lambertian(Arc::new(WoodTexture::walnut(2).with_axis(
+        c[1] + Vec3::new(-0.2_f32, 0_f32, -1.2_f32),
+        Vec3::new(1_f32, 0.1_f32, 0.2_f32),
+      ))),
+      lambertian(Arc::new(
+        MarbleTexture::carrara(3).with_direction(Vec3::new(
+          1_f32, 0.5_f32, 0.3_f32,
+        )),
+      )),
+      lambertian(Arc::new(GraniteTexture::grey(4))),
+    ])
+  }
+
+  /// Checkers and grids in texture space, nesting other textures, next to
+  /// a solid checker that ignores the parametrization.
+  fn uv_textures() -> (Arc<dyn Hitable>, CameraParameters) {
+    let color = |r: f32, g: f32, b: f32| -> Arc<dyn Texture> {
+      Arc::new(ConstantTexture::new(Vec3::new(r, g, b)))
+    };
+    let lambertian = |tex: Arc<dyn Texture>| -> Arc<dyn Material> {
+      Arc::new(Lambertian::new(tex))
+    };
+
+    //
+    // u runs around the equator, twice as far as v from pole to pole, so
+    // twice the tiles keeps them square.
+    WorldBuilder::material_lineup(vec![
+      lambertian(Arc::new(UvCheckerTexture::new(
+        color(0.1_f32, 0.1_f32, 0.1_f32),
+        color(0.9_f32, 0.9_f32, 0.9_f32),
+        16,
+        8,
+      ))),
+      lambertian(Arc::new(GridTexture::new(
+        color(0.15_f32, 0.15_f32, 0.15_f32),
+        Arc::new(MarbleTexture::carrara(1)),
+        12,
+        6,
+        0.06_f32,
+      ))),
+      lambertian(Arc::new(UvCheckerTexture::new(
+        Arc::new(WoodTexture::walnut(2)),
+        Arc::new(GraniteTexture::pink(3)),
+        8,
+        4,
+      ))),
+      lambertian(Arc::new(SolidCheckerTexture::new(
+        color(0.7_f32, 0.2_f32, 0.1_f32),
+        color(0.9_f32, 0.8_f32, 0.5_f32),
+        2.5_f32,
+      ))),
+    ])
+  }
+
+  /// Looks built by wiring textures together: a rotated checker, marble
+  /// stretched into streaks, wood blended into granite by a noise mask and
+  /// grimy tiles.
+  fn texture_graph() -> (Arc<dyn Hitable>, CameraParameters) {
+    let color = |r: f32, g: f32, b: f32| -> Arc<dyn Texture> {
+      Arc::new(ConstantTexture::new(Vec3::new(r, g, b)))
+    };
+    let lambertian = |tex: Arc<dyn Texture>| -> Arc<dyn Material> {
+      Arc::new(Lambertian::new(tex))
+    };
+
+    let checker = Arc::new(
+      UvTransform::new(Arc::new(UvCheckerTexture::new(
+        color(0.1_f32, 0.2_f32, 0.5_f32),
+        color(0.9_f32, 0.85_f32, 0.7_f32),
+        1,
+        1,
+      )))
+      .with_scale(16_f32, 8_f32)
+      .with_rotation(30_f32),
+    );
+
+    let streaks = Arc::new(
+      PositionTransform::new(Arc::new(MarbleTexture::nero_marquina(1)))
+        .with_scale(Vec3::new(1_f32, 0.25_f32, 1_f32))
+        .with_rotation(Vec3::new(1_f32, 0_f32, 0_f32), 90_f32),
+    );
+
+    //
+    // A hard edged mask: stretch the noise around 0.5 and clamp it.
+    let mask = Arc::new(ClampTexture::new(
+      Arc::new(AddTexture::new(
+        Arc::new(ScaleTexture::new(
+          Arc::new(FbmTexture::new(
+            FractalNoise::new(2).with_frequency(1.5_f32),
+            ColorRamp::grayscale(),
+          )),
+          4_f32,
+        )),
+        color(-1.5_f32, -1.5_f32, -1.5_f32),
+      )),
+      0_f32,
+      1_f32,
+    ));
+    let inlay = Arc::new(MixTexture::new(
+      Arc::new(WoodTexture::oak(3)),
+      Arc::new(GraniteTexture::pink(4)),
+      mask,
+    ));
+
+    let grime = Arc::new(RampTexture::new(
+      Arc::new(InvertTexture::new(Arc::new(TurbulenceTexture::new(
+        FractalNoise::new(5).with_frequency(3_f32),
+        ColorRamp::grayscale(),
+      )))),
+      ColorRamp::new(vec![
+        (0.4_f32, Vec3::new(0.3_f32, 0.25_f32, 0.2_f32)),
+        (0.9_f32, Vec3::same(1_f32)),
+      ]),
+    ));
+    let tiles = Arc::new(MultiplyTexture::new(
+      Arc::new(GridTexture::new(
+        color(0.2_f32, 0.2_f32, 0.2_f32),
+        color(0.3_f32, 0.7_f32, 0.6_f32),
+        12,
+        6,
+        0.08_f32,
+      )),
+      grime,
+    ));
+
+    WorldBuilder::material_lineup(vec![
+      lambertian(checker),
+      lambertian(streaks),
+      lambertian(inlay),
+      lambertian(tiles),
+    ])
+  }
+
+  /// A black and white checker image with `cells` squares along each
+  /// side.
+  fn checker_image(size: u32, cells: u32) -> ImageTexture {
+    let cell = size / cells;
+    let pixels = (0..size * size)
+      .map(|i| {
+        let (x, y) = ((i % size) / cell, (i / size) / cell);
+        Vec3::same(if (x + y) % 2 == 0 { 0.9_f32 } else { 0.05_f32 })
+      })
+      .collect();
+
+    ImageTexture::from_pixels(size, size, pixels)
+  }
+
+  /// A checker floor running to the horizon in three strips, filtered
+  /// bilinearly, trilinearly and with EWA from left to right, and a
+  /// mirror ball reflecting it.
+  fn texture_filtering() -> (Arc<dyn Hitable>, CameraParameters) {
+    let strip = |x0: f32, filter: TextureFilter| -> Arc<dyn Hitable> {
+      let image =
+        Arc::new(WorldBuilder::checker_image(256, 8).with_filter(filter));
+
+      //
+      // One repeat of the image every 4 units along the strip.
+      let tiled =
+        Arc::new(UvTransform::new(image).with_scale(1_f32, 52.5_f32));
+      Arc::new(XZRect::new(
+        x0,
+        x0 + 4_f32,
+        -200_f32,
+        10_f32,
+        0_f32,
+        Arc::new(Lambertian::new(tiled)),
+      ))
+    };
+    let light = Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::same(
+      1.5_f32,
+    ))));
+
+    let mut world = HitableList::new();
+    world.add_object(strip(-6_f32, TextureFilter::Bilinear));
+    world.add_object(strip(-2_f32, TextureFilter::Trilinear));
+    world.add_object(strip(2_f32, TextureFilter::Ewa));
+    world.add_object(Arc::new(FlipNormals::new(Arc::new(XZRect::new(
+      -100_f32, 100_f32, -300_f32, 50_f32, 20_f32, light,
+    )))));
+    world.add_object(Arc::new(Sphere::new(
+      Vec3::new(0_f32, 0.8_f32, 4_f32),
+      0.8_f32,
+      Arc::new(Metal::new(Vec3::same(0.9_f32), 0_f32)),
+    )));
+
+    let cam_params = CameraParameters {
+      lookfrom: Vec3::new(0_f32, 1.5_f32, 12_f32),
+      lookat: Vec3::new(0_f32, 0.3_f32, 0_f32),
+      world_up: Vec3::new(0_f32, 1_f32, 0_f32),
+      focus_dist: 12_f32,
+      aperture: 0_f32,
+      field_of_view: 40_f32,
+      time0: 0_f32,
+      time1: 1_f32,
+    };
+
+    (Arc::new(world), cam_params)
+  }
+
+  /// A grid wrapped by the sphere's own (u, v), pinching at the poles,
+  /// next to the same grid projected triplanar with sharp and soft blends,
+  /// and a projected checker image on a pair of panels.
+  fn triplanar() -> (Arc<dyn Hitable>, CameraParameters) {
+    let c = WorldBuilder::lineup_centers(4);
+    //
+    // Oren-Nayar at zero roughness is Lambertian, and lights the upright
+    // panels properly.
+    let diffuse = |tex: Arc<dyn Texture>| -> Arc<dyn Material> {
+      Arc::new(OrenNayar::new(tex, 0_f32))
+    };
+    let grid = || -> Arc<dyn Texture> {
+      Arc::new(GridTexture::new(
+        Arc::new(ConstantTexture::same(0.05_f32)),
+        Arc::new(ConstantTexture::new(Vec3::new(0.9_f32, 0.6_f32, 0.2_f32))),
+        1,
+        1,
+        0.1_f32,
+      ))
+    };
+    let image = diffuse(Arc::new(Triplanar::new(
+      Arc::new(WorldBuilder::checker_image(256, 8)),
+      0.5_f32,
+    )));
+
+    //
+    // The grid on the first sphere has as many cells as the projected
+    // ones have across its equator.
+    WorldBuilder::object_lineup(vec![
+      Arc::new(Sphere::new(
+        c[0],
+        1_f32,
+        diffuse(Arc::new(
+          UvTransform::new(grid()).with_scale(12_f32, 6_f32),
+        )),
+      )),
+      Arc::new(Sphere::new(
+        c[1],
+        1_f32,
+        diffuse(Arc::new(Triplanar::new(grid(), 2_f32))),
+      )),
+      Arc::new(Sphere::new(
+        c[2],
+        1_f32,
+        diffuse(Arc::new(
+          Triplanar::new(grid(), 2_f32).with_sharpness(1_f32),
+        )),
+      )),
+      Arc::new(XYRect::new(
+        c[3].x - 1_f32,
+        c[3].x + 1_f32,
+        0_f32,
+        2_f32,
+        -0.5_f32,
+        image.clone(),
+      )),
+      Arc::new(YZRect::new(
+        0_f32,
+        2_f32,
+        -0.5_f32,
+        1.5_f32,
+        c[3].x - 1_f32,
+        image,
+      )),
+    ])
+  }
+
+  /// A checker cut out of a sphere, a noise eaten sphere, a half
+  /// transparent ghost and a fence, all casting shadows through the holes.
+  fn cutouts() -> (Arc<dyn Hitable>, CameraParameters) {
+    let color = |r: f32, g: f32, b: f32| -> Arc<dyn Material> {
+      Arc::new(Lambertian::new(Arc::new(ConstantTexture::new(Vec3::new(
+        r, g, b,
+      )))))
+    };
+    let c = WorldBuilder::lineup_centers(3);
+
+    let checker = Arc::new(CheckerTexture::new(
+      Arc::new(ConstantTexture::same(0_f32)),
+      Arc::new(ConstantTexture::same(1_f32)),
+    ));
+    let fence_slats = Arc::new(CheckerTexture::new(
+      Arc::new(ConstantTexture::same(1_f32)),
+      Arc::new(ConstantTexture::same(0.2_f32)),
+    ));
+
+    WorldBuilder::object_lineup(vec![
+      Arc::new(AlphaMask::new(
+        Arc::new(Sphere::new(c[0], 1_f32, color(0.8_f32, 0.3_f32, 0.1_f32))),
+        checker,
+        0.5_f32,
+      )),
+      Arc::new(AlphaMask::new(
+        Arc::new(Sphere::new(c[1], 1_f32, color(0.2_f32, 0.6_f32, 0.2_f32))),
+        Arc::new(NoiseTexture::new(5_f32)),
+        0.45_f32,
+      )),
+      Arc::new(AlphaMask::stochastic(
+        Arc::new(Sphere::new(c[2], 1_f32, color(0.8_f32, 0.8_f32, 0.9_f32))),
+        Arc::new(ConstantTexture::same(0.4_f32)),
+      )),
+      Arc::new(AlphaMask::new(
+        Arc::new(XYRect::new(
+          -5_f32,
+          5_f32,
+          0_f32,
+          1.5_f32,
+          2_f32,
+          color(0.6_f32, 0.45_f32, 0.3_f32),
+        )),
+        fence_slats,
+        0.5_f32,
+      )),
+    ])
+  }
+
+  /// A tangent space normal map of round dimples in a square grid, as an
+  /// artist would paint it.
+  fn dimple_normal_map(size: u32, cells: u32) -> ImageTexture {
+    let cell = size as f32 / cells as f32;
+    let pixels = (0..size * size)
+      .map(|i| {
+        let x = ((i % size) as f32 + 0.5_f32) % cell / cell * 2_f32 - 1_f32;
+        let y = ((i / size) as f32 + 0.5_f32) % cell / cell * 2_f32 - 1_f32;
+
+        //
+        // Rows run from the top, so the bitangent points to -y.
+        let r2 = x * x + y * y;
+        let n = if r2 < 0.64_f32 {
+          Vec3::new(x, -y, (1_f32 - r2).sqrt())
+        } else {
+          Vec3::new(0_f32, 0_f32, 1_f32)
+        };
+        0.5_f32 * (n + Vec3::same(1_f32))
+      })
+      .collect();
+
+    ImageTexture::from_pixels(size, size, pixels)
+  }
+
+  /// Procedural bumps on gold and plaster, glass with a bumpy surface and
+  /// a normal mapped ball.
+  fn bump_mapping() -> (Arc<dyn Hitable>, CameraParameters) {
+    let plaster: Arc<dyn Material> = Arc::new(OrenNayar::new(
+      Arc::new(ConstantTexture::new(Vec3::new(0.8_f32, 0.75_f32, 0.7_f32))),
+      20_f32,
+    ));
+    let plastic: Arc<dyn Material> = Arc::new(
+      Principled::new(Arc::new(ConstantTexture::new(Vec3::new(
+        0.1_f32, 0.3_f32, 0.7_f32,
+      ))))
+      .with_roughness(Arc::new(ConstantTexture::same(0.2_f32))),
+    );
+
+    WorldBuilder::material_lineup(vec![
+      Arc::new(BumpMap::new(
+        Arc::new(Conductor::gold(0.1_f32)),
+        Arc::new(NoiseTexture::new(6_f32)),
+        0.02_f32,
+      )),
+      Arc::new(BumpMap::new(
+        plaster,
+        Arc::new(NoiseTexture::new(12_f32)),
+        0.01_f32,
+      )),
+      Arc::new(BumpMap::new(
+        Arc::new(Dielectric::new(1.5_f32)),
+        Arc::new(NoiseTexture::new(3_f32)),
+        0.03_f32,
+      )),
+      Arc::new(NormalMap::new(
+        plastic,
+        Arc::new(WorldBuilder::dimple_normal_map(256, 16)),
+        1_f32,
+      )),
+    ])
+  }
+
+  /// Wax, skin, marble and jade, lit from above so the light bleeding
+  /// through the lower halves shows.
+  fn subsurface() -> (Arc<dyn Hitable>, CameraParameters) {
+    WorldBuilder::material_lineup(vec![
+      Arc::new(Subsurface::new(
+        1.45_f32,
+        Vec3::new(4_f32, 3.5_f32, 2.5_f32),
+        Vec3::new(0.05_f32, 0.2_f32, 0.8_f32),
+        0.4_f32,
+      )),
+      Arc::new(Subsurface::new(
+        1.4_f32,
+        Vec3::new(3_f32, 4_f32, 4.5_f32),
+        Vec3::new(0.1_f32, 0.6_f32, 1.2_f32),
+        0.8_f32,
+      )),
+      Arc::new(Subsurface::new(
+        1.5_f32,
+        Vec3::same(6_f32),
+        Vec3::same(0.03_f32),
+        0_f32,
+      )),
+      Arc::new(Subsurface::new(
+        1.6_f32,
+        Vec3::new(2_f32, 3_f32, 2.5_f32),
+        Vec3::new(1.5_f32, 0.2_f32, 0.8_f32),
+        0.3_f32,
+      )),
+    ])
+  }
+
+  /// Red velvet, blue satin, a cream fabric with a tinted sheen and
+  /// noise-patterned upholstery.
+  fn cloth() -> (Arc<dyn Hitable>, CameraParameters) {
+    let color = |r: f32, g: f32, b: f32| -> Arc<dyn Texture> {
+      Arc::new(ConstantTexture::new(Vec3::new(r, g, b)))
+    };
+
+    WorldBuilder::material_lineup(vec![
+      Arc::new(Cloth::new(
+        color(0.3_f32, 0.02_f32, 0.03_f32),
+        color(1_f32, 0.3_f32, 0.3_f32),
+        0.3_f32,
+      )),
+      Arc::new(Cloth::new(
+        color(0.05_f32, 0.1_f32, 0.4_f32),
+        color(0.6_f32, 0.7_f32, 1_f32),
+        0.8_f32,
+      )),
+      Arc::new(Cloth::new(
+        color(0.7_f32, 0.65_f32, 0.5_f32),
+        color(1_f32, 0.8_f32, 0.4_f32),
+        0.5_f32,
+      )),
+      Arc::new(Cloth::new(
+        color(0.2_f32, 0.3_f32, 0.1_f32),
+        Arc::new(NoiseTexture::new(4_f32)),
+        0.4_f32,
+      )),
+    ])
+  }
+
+  /// Brushed aluminium: isotropic, brushed around the poles, brushed from
+  /// pole to pole, and brushed along a fixed diagonal.
+  fn brushed_metal() -> (Arc<dyn Hitable>, CameraParameters) {
+    WorldBuilder::material_lineup(vec![
+      Arc::new(Conductor::aluminium(0.3_f32)),
+      Arc::new(Conductor::aluminium(0_f32).anisotropic(0.05_f32, 0.5_f32)),
+      Arc::new(Conductor::aluminium(0_f32).anisotropic(0.5_f32, 0.05_f32)),
+      Arc::new(
+        Conductor::aluminium(0_f32)
+          .anisotropic(0.05_f32, 0.5_f32)
+          .with_tangent(Vec3::new(1_f32, 1_f32, 0_f32)),
+      ),
+    ])
+  }
+
+  /// A soap bubble, a coated lens and two metals with interference films,
+  /// the outer ones swirled by noise.
+  fn iridescent() -> (Arc<dyn Hitable>, CameraParameters) {
+    let swirl = || -> Arc<dyn Texture> { Arc::new(NoiseTexture::new(2_f32)) };
+    let uniform = || -> Arc<dyn Texture> {
+      Arc::new(ConstantTexture::same(0_f32))
+    };
+
+    WorldBuilder::material_lineup(vec![
+      Arc::new(Dielectric::new(1_f32).with_thin_film(ThinFilm::new(
+        1.33_f32,
+        swirl(),
+        200_f32,
+        900_f32,
+      ))),
+      Arc::new(Dielectric::new(1.5_f32).with_thin_film(ThinFilm::new(
+        1.38_f32,
+        uniform(),
+        250_f32,
+        250_f32,
+      ))),
+      Arc::new(
+        Metal::new(Vec3::same(0.6_f32), 0.05_f32).with_thin_film(
+          ThinFilm::new(2_f32, uniform(), 200_f32, 200_f32),
+        ),
+      ),
+      Arc::new(
+        Metal::new(Vec3::new(0.9_f32, 0.7_f32, 0.4_f32), 0_f32)
+          .with_thin_film(ThinFilm::new(1.5_f32, swirl(), 100_f32, 600_f32)),
+      ),
+    ])
+  }
+
+  /// Lambertian next to increasingly rough Oren-Nayar clay.
+  fn rough_diffuse() -> (Arc<dyn Hitable>, CameraParameters) {
+    let clay: Arc<dyn Texture> = Arc::new(ConstantTexture::new(Vec3::new(
+      0.75_f32, 0.45_f32, 0.3_f32,
+    )));
+
+    WorldBuilder::material_lineup(vec![
+      Arc::new(Lambertian::new(clay.clone())),
+      Arc::new(OrenNayar::new(clay.clone(), 20_f32)),
+      Arc::new(OrenNayar::new(clay.clone(), 45_f32)),
+      Arc::new(OrenNayar::new(clay, 90_f32)),
+    ])
+  }
+
+  /// A one-sided sign with noise driven emission behind a row of spheres,
+  /// a sphere light and an overhead panel given in power units.
+  fn emitters() -> (Arc<dyn Hitable>, CameraParameters) {
+    let grey = Arc::new(Lambertian::new(Arc::new(ConstantTexture::same(
+      0.5_f32,
+    ))));
+    let white: Arc<dyn Texture> = Arc::new(ConstantTexture::same(1_f32));
+
+    let mut world = HitableList::new();
+    world.add_object(Arc::new(Sphere::new(
+      Vec3::new(0_f32, -1000_f32, 0_f32),
+      1000_f32,
+      grey.clone(),
+    )));
+    for i in 0..3 {
+      world.add_object(Arc::new(Sphere::new(
+        Vec3::new(-3_f32 + 3_f32 * i as f32, 1_f32, 0_f32),
+        1_f32,
+        grey.clone(),
+      )));
+    }
+
+    //
+    // Only the side facing the camera lights up.
+    let sign = Arc::new(
+      DiffuseLight::new(Arc::new(NoiseTexture::new(3_f32)))
+        .with_scale(4_f32)
+        .one_sided(),
+    );
+    world.add_object(Arc::new(XYRect::new(
+      -6_f32, 6_f32, 0.5_f32, 3.5_f32, -3_f32, sign,
+    )));
+
+    //
+    // These lights are given by their power, so the shapes are built first
+    // and the power spread over their area. A closed shape only shows its
+    // outside, so it emits one-sided.
+    let mut bulb =
+      Sphere::new(Vec3::new(4.5_f32, 3_f32, 2_f32), 0.3_f32, grey.clone());
+    bulb.mtl = Arc::new(
+      DiffuseLight::new(Arc::new(ConstantTexture::new(Vec3::new(
+        1_f32, 0.5_f32, 0.2_f32,
+      ))))
+      .one_sided()
+      .with_power(60_f32, bulb.area()),
+    );
+    world.add_object(Arc::new(bulb));
+
+    let mut panel = XZRect::new(-3_f32, 3_f32, -1_f32, 3_f32, 7_f32, grey);
+    panel.mtl = Arc::new(
+      DiffuseLight::new(white)
+        .one_sided()
+        .with_power(150_f32, panel.area()),
+    );
+    world.add_object(Arc::new(FlipNormals::new(Arc::new(panel))));
+
+    let cam_params = CameraParameters {
+      lookfrom: Vec3::new(0_f32, 3_f32, 14_f32),
+      lookat: Vec3::new(0_f32, 1.5_f32, 0_f32),
+      world_up: Vec3::new(0_f32, 1_f32, 0_f32),
+      focus_dist: 14_f32,
+      aperture: 0_f32,
+      field_of_view: 35_f32,
+      time0: 0_f32,
+      time1: 1_f32,
+    };
+
+    (Arc::new(world), cam_params)
+  }
+
+  fn default_camera() -> CameraParameters {
+    CameraParameters {
+      lookfrom: Vec3::new(13f32, 2f32, 3f32),
+      lookat: Vec3::new(0f32, 0f32, 0f32),
+      world_up: Vec3::new(0_f32, 1_f32, 0_f32),
+      focus_dist: 10f32,
+      aperture: 0.1f32,
+      field_of_view: 20_f32,
+      time0: 0_f32,
+      time1: 1_f32,
+    }
   }
 }
 
-const THREAD_COUNT: i32 = 4;
-const WORK_TILE_SIZE: u32 = 4;
+impl WorldBuilder {
+  /// The scene called `name`. Scenes that are generated randomly draw
+  /// from `seed`, so the same seed always gives the same world.
+  fn from_name(
+    name: &str,
+    seed: u64,
+  ) -> Option<(Arc<dyn Hitable>, CameraParameters)> {
+    match name {
+      "cornell_box" => Some(WorldBuilder::cornell_box()),
+      "random_world" => Some(WorldBuilder::random_world(seed)),
+      "random_world_bvh" => Some(WorldBuilder::random_world_bvh(seed)),
+      "two_spheres" => Some(WorldBuilder::two_spheres()),
+      "two_perlin_spheres" => Some(WorldBuilder::two_perlin_spheres()),
+      "simple_light" => Some(WorldBuilder::simple_light()),
+      "metals" => Some(WorldBuilder::metals()),
+      "frosted_glass" => Some(WorldBuilder::frosted_glass()),
+      "tinted_glass" => Some(WorldBuilder::tinted_glass()),
+      "principled" => Some(WorldBuilder::principled()),
+      "layered" => Some(WorldBuilder::layered()),
+      "emitters" => Some(WorldBuilder::emitters()),
+      "rough_diffuse" => Some(WorldBuilder::rough_diffuse()),
+      "iridescent" => Some(WorldBuilder::iridescent()),
+      "brushed_metal" => Some(WorldBuilder::brushed_metal()),
+      "cloth" => Some(WorldBuilder::cloth()),
+      "subsurface" => Some(WorldBuilder::subsurface()),
+      "bump_mapping" => Some(WorldBuilder::bump_mapping()),
+      "cutouts" => Some(WorldBuilder::cutouts()),
+      "fractal_noise" => Some(WorldBuilder::fractal_noise()),
+      "cellular" => Some(WorldBuilder::cellular()),
+      "wood_and_stone" => Some(WorldBuilder::wood_and_stone()),
+      "uv_textures" => Some(WorldBuilder::uv_textures()),
+      "texture_graph" => Some(WorldBuilder::texture_graph()),
+      "texture_filtering" => Some(WorldBuilder::texture_filtering()),
+      "triplanar" => Some(WorldBuilder::triplanar()),
+      _ => None,
+    }
+  }
+}
+
+fn exit_with_usage(msg: &str) -> ! {
+  eprintln!("{}\n{}", msg, options::USAGE);
+  std::process::exit(1);
+}
 
 fn main() {
-  let nx = 1200;
-  let ny = 800;
-  const RAYS_PER_PIXEL: u32 = 128;
+  let mut opts = match RenderOptions::from_args(std::env::args().skip(1)) {
+    Ok(opts) => opts,
+    Err(e) => exit_with_usage(&e),
+  };
+
+  //
+  // A resumed render takes its scene, resolution and filter from the
+  // checkpoint, whatever the command line says.
+  let resumed = opts.resume.clone().map(|path| {
+    let (header, film) = checkpoint::load(&path).unwrap_or_else(|e| {
+      exit_with_usage(&format!("failed to load checkpoint {}: {}", path, e))
+    });
+
+    if opts.spp <= header.spp {
+      exit_with_usage(&format!(
+        "checkpoint already has {} samples per pixel, pass a higher --spp",
+        header.spp
+      ));
+    }
+
+    println!(
+      "Resuming {} ({}x{}) at {} samples per pixel",
+      header.scene, film.width, film.height, header.spp
+    );
+
+    opts.scene = header.scene.clone();
+    opts.scene_seed = header.scene_seed;
+    opts.width = film.width;
+    opts.height = film.height;
+    opts.filter = header.filter;
+    opts.filter_radius = Some(header.filter_radius);
+
+    (header, film)
+  });
+
+  let checkpoint_path = opts.checkpoint.clone().or_else(|| opts.resume.clone());
 
-  let window = Window::new(0, nx, 0, ny);
+  let (nx, ny) = (opts.width, opts.height);
 
   let (world, cam_params) =
-        //WorldBuilder::two_perlin_spheres();
-//            WorldBuilder::random_world();
-  //          WorldBuilder::two_spheres();
-          //WorldBuilder::random_world_bvh();
-  //            WorldBuilder::simple_light();
-    WorldBuilder::cornell_box();
+    match WorldBuilder::from_name(&opts.scene, opts.scene_seed) {
+      Some(scene) => scene,
+      None => exit_with_usage(&format!("unknown scene {}", opts.scene)),
+    };
 
   let cam = Camera::new(
     cam_params.lookfrom,
@@ -405,111 +1406,158 @@ fn main() {
     cam_params.time1,
   );
 
-  let domains = {
-    let mut d = Vec::new();
-    let work_x = window.width() / WORK_TILE_SIZE;
-    let work_y = window.height() / WORK_TILE_SIZE;
-
-    println!("Work_x {} :: Work_y {}", work_x, work_y);
-
-    for y in 0..WORK_TILE_SIZE {
-      for x in 0..WORK_TILE_SIZE {
-        d.push(Window::new(
-          x * work_x,
-          (x + 1) * work_x,
-          y * work_y,
-          (y + 1) * work_y,
-        ));
-      }
-    }
-
-    println!("Work packages {}", d.len());
-    Arc::new(Mutex::new(d))
+  let (film, sampler, start_spp) = match resumed {
+    Some((header, film)) => (film, header.sampler, header.spp),
+    None => (
+      Film::new(nx, ny, opts.filter.create(opts.filter_radius())),
+      Sampler::new(opts.seed),
+      0,
+    ),
   };
 
-  let (tx, rx) = mpsc::channel();
-
-  let mut threads = Vec::new();
-
-  let tmr = BasicTimer::new();
+  let mut renderer = Renderer::new(world, cam, film, sampler);
+  renderer.set_schedule(TileSchedule {
+    threads: opts.threads,
+    tile_size: opts.tile_size,
+    order: opts.tile_order,
+  });
+
+  let progress_bar = Arc::new(Mutex::new(ProgressBar::new(30)));
+  {
+    let progress_bar = progress_bar.clone();
+    renderer.set_progress_callback(move |p| {
+      progress_bar.lock().unwrap().update(p);
+    });
+  }
 
-  for i in 0..THREAD_COUNT {
-    let work_packages = domains.clone();
-    let tx = tx.clone();
-    let world = world.clone();
+  {
+    let cancel = renderer.cancel_flag();
+    ctrlc::set_handler(move || {
+      println!("Interrupted, stopping the tiles in flight");
+      cancel.store(true, Ordering::SeqCst);
+    })
+    .expect("Failed to install the Ctrl-C handler!");
+  }
 
-    let thread = thread::spawn(move || loop {
-      let mut rng = thread_rng();
+  let save = |film: &Film| {
+    write_image(&opts.output, nx, ny, &film.to_rgb8())
+      .expect("Failed to write image!");
+  };
 
-      let current_work_package = {
-        let mut work_queue = work_packages.lock().unwrap();
-        work_queue.pop()
+  let save_checkpoint = |renderer: &Renderer, spp: u32| {
+    if let Some(path) = &checkpoint_path {
+      progress_bar.lock().unwrap().finish();
+
+      let header = CheckpointHeader {
+        scene: opts.scene.clone(),
+        scene_seed: opts.scene_seed,
+        filter: opts.filter,
+        filter_radius: opts.filter_radius(),
+        spp,
+        sampler: renderer.sampler(),
       };
 
-      if !current_work_package.is_some() {
-        println!("Thread {} out of work, shutting down", i);
-        break;
+      match checkpoint::save(path, &header, &renderer.film()) {
+        Ok(()) => println!("Checkpoint saved to {} at {} spp", path, spp),
+        Err(e) => eprintln!("Failed to save checkpoint {}: {}", path, e),
       }
+    }
+  };
 
-      let current_work_package = current_work_package.unwrap();
-
-      let mut pixels = Vec::new();
-
-      for y in current_work_package.ymin..current_work_package.ymax {
-        for x in current_work_package.xmin..current_work_package.xmax {
-          let mut col = Vec3::same(0f32);
-
-          for _ in 0..RAYS_PER_PIXEL {
-            let dx: f32 = rng.gen();
-            let u = (x as f32 + dx) / nx as f32;
-
-            let dy: f32 = rng.gen();
-            let v = (y as f32 + dy) / ny as f32;
-
-            let r = cam.ray_at(u, v);
-            col += color(&r, &world, 0);
-          }
+  println!(
+    "Rendering {}x{} with {} threads, {}x{} tiles in {:?} order",
+    nx, ny, opts.threads, opts.tile_size, opts.tile_size, opts.tile_order
+  );
 
-          col /= RAYS_PER_PIXEL as f32;
-          col = Vec3::new(col.x.sqrt(), col.y.sqrt(), col.z.sqrt());
-          let pixel_color = to_rgb8(col);
-          pixels.push(pixel_color);
-        }
-      }
+  let tmr = BasicTimer::new();
 
-      tx.send((i, current_work_package, pixels)).unwrap();
-    });
-    threads.push(thread);
-  }
+  if opts.progressive || checkpoint_path.is_some() {
+    let settings = ProgressiveSettings {
+      start_spp,
+      target_spp: opts.spp,
+      time_budget: opts.time_budget,
+      preview_interval: opts.preview_interval,
+      preview_passes: opts.preview_passes,
+      checkpoint_interval: checkpoint_path
+        .as_ref()
+        .map(|_| opts.checkpoint_interval),
+    };
 
-  for t in threads {
-    t.join().unwrap();
+    let spp =
+      render_progressive(&mut renderer, &settings, save, save_checkpoint);
+    progress_bar.lock().unwrap().finish();
+    println!("Progressive render stopped at {} samples per pixel", spp);
+  } else {
+    renderer.render_pass(opts.spp, |_| {});
+    progress_bar.lock().unwrap().finish();
   }
 
   tmr.end();
-  println!(
-    "Raytraced using {} threads, total time {} seconds",
-    THREAD_COUNT,
-    tmr.elapsed_seconds()
-  );
-
-  std::mem::drop(tx);
-
-  let mut image_pixels = Vec::new();
-  image_pixels.resize((nx * ny) as usize, RGB8::new(0, 0, 0));
+  println!("Raytraced in {} seconds", tmr.elapsed_seconds());
 
-  for (tid, wpkg, pixels) in rx {
-    println!("Merging work package {:?} from thread {}", wpkg, tid);
+  save(&renderer.film());
+}
 
-    let mut idx = 0;
-    for y in wpkg.ymin..wpkg.ymax {
-      for x in wpkg.xmin..wpkg.xmax {
-        image_pixels[((ny - y - 1) * nx + x) as usize] = pixels[idx];
-        idx += 1;
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::filter::FilterKind;
+  use crate::ray::Ray;
+
+  /// What rays dropped onto the scene from above see: where they stop and
+  /// the colour of what they hit.
+  fn survey(world: &dyn Hitable) -> Vec<(f32, Vec3)> {
+    let mut seen = Vec::new();
+    for i in -20..20 {
+      for j in -20..20 {
+        let origin =
+          Vec3::new(i as f32 * 0.55_f32, 5_f32, j as f32 * 0.55_f32);
+        let r = Ray::new(origin, Vec3::new(0_f32, -1_f32, 0_f32), 0_f32);
+        if let Some(h) = world.hit(&r, 0.001_f32, f32::MAX) {
+          //
+          // Fuzzy metals absorb the odd ray, so try a few times.
+          let albedo = (0..16).find_map(|_| h.mtl.scatter(&r, &h));
+          let albedo = albedo.map(|(a, _)| a);
+          seen.push((h.t, albedo.unwrap_or_else(|| Vec3::same(0_f32))));
+        }
       }
     }
+    seen
   }
 
-  write_image("raytraced.png", nx as u32, ny as u32, &image_pixels)
-    .expect("Failed to write image!");
+  #[test]
+  fn test_resumed_random_world_is_the_same() {
+    let header = CheckpointHeader {
+      scene: "random_world".to_string(),
+      scene_seed: 42,
+      filter: FilterKind::Box,
+      filter_radius: 0.5_f32,
+      spp: 4,
+      sampler: Sampler::new(0),
+    };
+    let film = Film::new(1, 1, header.filter.create(header.filter_radius));
+
+    let path = std::env::temp_dir()
+      .join(format!("raytracer-scene-test-{}.ckpt", std::process::id()));
+    checkpoint::save(&path, &header, &film).unwrap();
+    let (loaded, _) = checkpoint::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let (first, _) =
+      WorldBuilder::from_name(&header.scene, header.scene_seed).unwrap();
+    let (resumed, _) =
+      WorldBuilder::from_name(&loaded.scene, loaded.scene_seed).unwrap();
+    let (a, b) = (survey(first.as_ref()), survey(resumed.as_ref()));
+    assert_eq!(a.len(), b.len());
+    for ((ta, ca), (tb, cb)) in a.iter().zip(b.iter()) {
+      //
+      // Glass reflects or refracts at random, which rounds its colour
+      // differently.
+      assert_eq!(ta, tb);
+      assert!((*ca - *cb).length() < 1e-4_f32, "{:?} {:?}", ca, cb);
+    }
+
+    let (other, _) = WorldBuilder::from_name(&header.scene, 43).unwrap();
+    assert_ne!(survey(first.as_ref()), survey(other.as_ref()));
+  }
 }
@@ -0,0 +1,130 @@
+use super::perlin::PerlinNoise;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::{dot_product, unit_vector, Vec3};
+
+/// Veins in a light stone: bands along `direction` bent by turbulence.
+pub struct MarbleTexture {
+  noise: PerlinNoise,
+  /// Bands per world unit along `direction`. The turbulence is scaled
+  /// with them, so the whole pattern shrinks or grows.
+  pub scale: f32,
+  pub direction: Vec3,
+  /// How strongly turbulence bends the bands.
+  pub turbulence: f32,
+  /// Higher values make thinner veins.
+  pub sharpness: f32,
+  pub base: Vec3,
+  pub vein: Vec3,
+}
+
+impl MarbleTexture {
+  pub fn new(seed: u64, base: Vec3, vein: Vec3) -> MarbleTexture {
+    MarbleTexture {
+      noise: PerlinNoise::with_seed(seed),
+      scale: 4_f32,
+      direction: Vec3::new(0_f32, 0_f32, 1_f32),
+      turbulence: 10_f32,
+      sharpness: 4_f32,
+      base,
+      vein,
+    }
+  }
+
+  /// White stone with grey veins.
+  pub fn carrara(seed: u64) -> MarbleTexture {
+    MarbleTexture::new(
+      seed,
+      Vec3::new(0.9_f32, 0.9_f32, 0.88_f32),
+      Vec3::new(0.35_f32, 0.37_f32, 0.4_f32),
+    )
+  }
+
+  /// Black stone with bold white veins.
+  pub fn nero_marquina(seed: u64) -> MarbleTexture {
+    MarbleTexture::new(
+      seed,
+      Vec3::same(0.04_f32),
+      Vec3::new(0.85_f32, 0.85_f32, 0.8_f32),
+    )
+    .with_sharpness(8_f32)
+  }
+
+  pub fn with_scale(mut self, scale: f32) -> MarbleTexture {
+    self.scale = scale;
+    self
+  }
+
+  pub fn with_direction(mut self, direction: Vec3) -> MarbleTexture {
+    self.direction = unit_vector(direction);
+    self
+  }
+
+  pub fn with_turbulence(mut self, turbulence: f32) -> MarbleTexture {
+    self.turbulence = turbulence;
+    self
+  }
+
+  pub fn with_sharpness(mut self, sharpness: f32) -> MarbleTexture {
+    self.sharpness = sharpness;
+    self
+  }
+}
+
+impl Texture for MarbleTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    let p = self.scale * at.p;
+    let s = (dot_product(p, self.direction)
+      + self.turbulence * self.noise.turbulence(p, 7))
+    .sin();
+
+    //
+    // Veins run where the bands cross zero.
+    let t = (1_f32 - s.abs()).powf(self.sharpness);
+    (1_f32 - t) * self.base + t * self.vein
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::noise_texture::{check_preset, sample_points};
+  use crate::vec3::cross_product;
+
+  #[test]
+  fn test_marble_presets() {
+    for &preset in [MarbleTexture::carrara, MarbleTexture::nero_marquina].iter()
+    {
+      let m = preset(0);
+      let lo = Vec3::new(
+        m.base.x.min(m.vein.x),
+        m.base.y.min(m.vein.y),
+        m.base.z.min(m.vein.z),
+      );
+      let hi = Vec3::new(
+        m.base.x.max(m.vein.x),
+        m.base.y.max(m.vein.y),
+        m.base.z.max(m.vein.z),
+      );
+      check_preset(preset, lo, hi);
+    }
+  }
+
+  #[test]
+  fn test_veins_follow_the_direction() {
+    //
+    // Without turbulence the bands change only along the direction.
+    let direction = unit_vector(Vec3::new(1_f32, 2_f32, 0.5_f32));
+    let across =
+      unit_vector(cross_product(direction, Vec3::new(0_f32, 0_f32, 1_f32)));
+    let marble = MarbleTexture::carrara(3)
+      .with_direction(direction)
+      .with_turbulence(0_f32);
+    let value = |p: Vec3| marble.value(0_f32, 0_f32, p);
+
+    for p in sample_points() {
+      assert!((value(p) - value(p + 0.7_f32 * across)).length() < 1e-4_f32);
+    }
+    let change = |p: Vec3| (value(p) - value(p + 0.1_f32 * direction)).length();
+    assert!(sample_points().iter().any(|&p| change(p) > 0.1_f32));
+  }
+}
@@ -5,7 +5,16 @@ use super::vec3::Vec3;
 pub trait Material: Send + Sync {
   fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)>;
 
-  fn emitted(&self, _u: f32, _v: f32, _p: Vec3) -> Vec3 {
+  /// Radiance emitted towards the origin of `r`, which hit the surface
+  /// at `h`.
+  fn emitted(&self, _r: &Ray, _h: &HitRecord) -> Vec3 {
     Vec3::same(0_f32)
   }
+
+  /// Whether scattering `r` at `h` is a step of a random walk through the
+  /// material's interior rather than a bounce off its surface. Such steps
+  /// don't count against the bounce limit.
+  fn is_medium_step(&self, _r: &Ray, _h: &HitRecord) -> bool {
+    false
+  }
 }
@@ -1,6 +1,8 @@
 use super::hitable::HitRecord;
 use super::material::Material;
 use super::ray::Ray;
+use super::ray_differential;
+use super::thin_film::{conductor_from_reflectivity, ThinFilm};
 use super::vec3::{
   dot_product, random_in_unit_sphere, reflect, unit_vector, Vec3,
 };
@@ -8,22 +10,49 @@ use super::vec3::{
 pub struct Metal {
   pub albedo: Vec3,
   pub fuzz: f32,
+  pub thin_film: Option<ThinFilm>,
 }
 
 impl Metal {
   pub fn new(albedo: Vec3, f: f32) -> Metal {
     let fuzz = if f < 1f32 { f } else { 1f32 };
 
-    Metal { albedo, fuzz }
+    Metal {
+      albedo,
+      fuzz,
+      thin_film: None,
+    }
+  }
+
+  /// Coats the metal with an interference film, like heat-tinted steel.
+  pub fn with_thin_film(mut self, film: ThinFilm) -> Metal {
+    self.thin_film = Some(film);
+    self
   }
 }
 
 impl Material for Metal {
   fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
     let reflected = reflect(unit_vector(r.direction), h.normal);
-    let scattered =
-      Ray::new(h.p, reflected + self.fuzz * random_in_unit_sphere(), r.time);
-    let attenuation = self.albedo;
+    let scattered = if self.fuzz > 0f32 {
+      Ray::new(h.p, reflected + self.fuzz * random_in_unit_sphere(), r.time)
+    } else {
+      Ray::new(h.p, reflected, r.time)
+        .with_differential(ray_differential::reflected(r, h))
+    };
+    let attenuation = match &self.thin_film {
+      Some(film) => {
+        let (eta, k) = conductor_from_reflectivity(self.albedo, self.albedo);
+        film.reflectance(
+          -dot_product(unit_vector(r.direction), h.normal),
+          film.thickness_at(r, h),
+          1f32,
+          eta,
+          k,
+        )
+      }
+      None => self.albedo,
+    };
 
     if dot_product(scattered.direction, h.normal) > 0f32 {
       Some((attenuation, scattered))
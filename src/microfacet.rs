@@ -0,0 +1,172 @@
+use super::vec3::{cross_product, unit_vector, Vec3};
+use std::f32::consts::PI;
+
+/// GGX / Trowbridge-Reitz microfacet distribution, with Smith's
+/// height-correlated masking-shadowing. All directions are in the local
+/// shading frame, with the normal along +z.
+#[derive(Copy, Clone, Debug)]
+pub struct GgxDistribution {
+  pub alpha_x: f32,
+  pub alpha_y: f32,
+}
+
+/// Below this the distribution is too peaked to evaluate reliably.
+const MIN_ALPHA: f32 = 1e-4_f32;
+
+impl GgxDistribution {
+  pub fn new(alpha_x: f32, alpha_y: f32) -> GgxDistribution {
+    GgxDistribution {
+      alpha_x: alpha_x.max(MIN_ALPHA),
+      alpha_y: alpha_y.max(MIN_ALPHA),
+    }
+  }
+
+  pub fn isotropic(alpha: f32) -> GgxDistribution {
+    GgxDistribution::new(alpha, alpha)
+  }
+
+  /// Maps a perceptually linear roughness in [0, 1] to the GGX alpha.
+  pub fn roughness_to_alpha(roughness: f32) -> f32 {
+    roughness * roughness
+  }
+
+  /// Density of microfacet normals `h`.
+  pub fn d(&self, h: Vec3) -> f32 {
+    if h.z <= 0_f32 {
+      return 0_f32;
+    }
+
+    let x = h.x / self.alpha_x;
+    let y = h.y / self.alpha_y;
+    let t = x * x + y * y + h.z * h.z;
+
+    1_f32 / (PI * self.alpha_x * self.alpha_y * t * t)
+  }
+
+  fn lambda(&self, w: Vec3) -> f32 {
+    if w.z == 0_f32 {
+      return 0_f32;
+    }
+
+    let ax = self.alpha_x * w.x;
+    let ay = self.alpha_y * w.y;
+    let tan2 = (ax * ax + ay * ay) / (w.z * w.z);
+
+    0.5_f32 * (-1_f32 + (1_f32 + tan2).sqrt())
+  }
+
+  /// Masking function for a single direction.
+  pub fn g1(&self, w: Vec3) -> f32 {
+    1_f32 / (1_f32 + self.lambda(w))
+  }
+
+  /// Joint masking-shadowing for the pair of directions.
+  pub fn g2(&self, wo: Vec3, wi: Vec3) -> f32 {
+    1_f32 / (1_f32 + self.lambda(wo) + self.lambda(wi))
+  }
+
+  /// Samples a microfacet normal from the distribution of normals visible
+  /// from `wo` (Heitz, "Sampling the GGX Distribution of Visible Normals",
+  /// 2018). `wo` must be in the upper hemisphere.
+  pub fn sample_visible_normal(&self, wo: Vec3, u1: f32, u2: f32) -> Vec3 {
+    //
+    // Stretch the view direction so the distribution becomes a unit
+    // hemisphere.
+    let vh =
+      unit_vector(Vec3::new(self.alpha_x * wo.x, self.alpha_y * wo.y, wo.z));
+
+    let lensq = vh.x * vh.x + vh.y * vh.y;
+    let t1 = if lensq > 0_f32 {
+      Vec3::new(-vh.y, vh.x, 0_f32) / lensq.sqrt()
+    } else {
+      Vec3::new(1_f32, 0_f32, 0_f32)
+    };
+    let t2 = cross_product(vh, t1);
+
+    //
+    // Uniform point on the disk, warped onto the visible half.
+    let r = u1.sqrt();
+    let phi = 2_f32 * PI * u2;
+    let p1 = r * phi.cos();
+    let p2 = r * phi.sin();
+    let s = 0.5_f32 * (1_f32 + vh.z);
+    let p2 = (1_f32 - s) * (1_f32 - p1 * p1).sqrt() + s * p2;
+
+    let nh = p1 * t1
+      + p2 * t2
+      + (1_f32 - p1 * p1 - p2 * p2).max(0_f32).sqrt() * vh;
+
+    unit_vector(Vec3::new(
+      self.alpha_x * nh.x,
+      self.alpha_y * nh.y,
+      nh.z.max(1e-6_f32),
+    ))
+  }
+
+  /// Density of `sample_visible_normal` returning `h`, over solid angle of
+  /// the normals.
+  pub fn visible_normal_pdf(&self, wo: Vec3, h: Vec3) -> f32 {
+    if wo.z <= 0_f32 {
+      return 0_f32;
+    }
+
+    let cos_oh = (wo.x * h.x + wo.y * h.y + wo.z * h.z).max(0_f32);
+    self.g1(wo) * cos_oh * self.d(h) / wo.z
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn spherical(theta: f32, phi: f32) -> Vec3 {
+    Vec3::new(
+      theta.sin() * phi.cos(),
+      theta.sin() * phi.sin(),
+      theta.cos(),
+    )
+  }
+
+  #[test]
+  fn test_ggx_projected_area_is_one() {
+    //
+    // The projected area of the microfacets onto the macro surface has to
+    // add up to one.
+    for &(ax, ay) in [(0.3_f32, 0.3_f32), (0.5_f32, 0.1_f32)].iter() {
+      let ggx = GgxDistribution::new(ax, ay);
+      let (n_theta, n_phi) = (512, 256);
+      let d_theta = 0.5_f32 * PI / n_theta as f32;
+      let d_phi = 2_f32 * PI / n_phi as f32;
+
+      let mut integral = 0_f64;
+      for i in 0..n_theta {
+        for j in 0..n_phi {
+          let theta = (i as f32 + 0.5_f32) * d_theta;
+          let phi = (j as f32 + 0.5_f32) * d_phi;
+          let h = spherical(theta, phi);
+          integral +=
+            (ggx.d(h) * h.z * theta.sin() * d_theta * d_phi) as f64;
+        }
+      }
+
+      assert!((integral - 1_f64).abs() < 0.01_f64, "{}", integral);
+    }
+  }
+
+  #[test]
+  fn test_visible_normals_face_the_viewer() {
+    let ggx = GgxDistribution::new(0.6_f32, 0.2_f32);
+    let wo = unit_vector(Vec3::new(0.7_f32, -0.2_f32, 0.3_f32));
+
+    for i in 0..16 {
+      for j in 0..16 {
+        let u1 = (i as f32 + 0.5_f32) / 16_f32;
+        let u2 = (j as f32 + 0.5_f32) / 16_f32;
+        let h = ggx.sample_visible_normal(wo, u1, u2);
+        assert!(h.z > 0_f32);
+        assert!(wo.x * h.x + wo.y * h.y + wo.z * h.z > -1e-4_f32);
+        assert!((h.length() - 1_f32).abs() < 1e-4_f32);
+      }
+    }
+  }
+}
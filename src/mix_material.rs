@@ -0,0 +1,44 @@
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::ray::Ray;
+use super::texture::Texture;
+use super::vec3::Vec3;
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// Blends two materials. Each scatter picks `b` with probability given by
+/// `weight` at the hit point, read as a single channel, and `a` otherwise.
+pub struct MixMaterial {
+  pub a: Arc<dyn Material>,
+  pub b: Arc<dyn Material>,
+  pub weight: Arc<dyn Texture>,
+}
+
+impl MixMaterial {
+  pub fn new(
+    a: Arc<dyn Material>,
+    b: Arc<dyn Material>,
+    weight: Arc<dyn Texture>,
+  ) -> MixMaterial {
+    MixMaterial { a, b, weight }
+  }
+
+  fn weight_at(&self, r: &Ray, h: &HitRecord) -> f32 {
+    self.weight.scalar_at(r, h).clamp(0_f32, 1_f32)
+  }
+}
+
+impl Material for MixMaterial {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    if thread_rng().gen::<f32>() < self.weight_at(r, h) {
+      self.b.scatter(r, h)
+    } else {
+      self.a.scatter(r, h)
+    }
+  }
+
+  fn emitted(&self, r: &Ray, h: &HitRecord) -> Vec3 {
+    let w = self.weight_at(r, h);
+    (1_f32 - w) * self.a.emitted(r, h) + w * self.b.emitted(r, h)
+  }
+}
@@ -2,7 +2,7 @@ use super::aabb::Aabb;
 use super::hitable::{HitRecord, Hitable};
 use super::material::Material;
 use super::ray::Ray;
-use super::sphere::get_sphere_uv;
+use super::sphere::{get_sphere_tangent, get_sphere_uv};
 use super::vec3::{dot_product, Vec3};
 use std::sync::Arc;
 
@@ -10,7 +10,7 @@ pub struct MovingSphere {
   pub center0: Vec3,
   pub center1: Vec3,
   pub radius: f32,
-  pub mtl: Arc<Material>,
+  pub mtl: Arc<dyn Material>,
   pub time0: f32,
   pub time1: f32,
 }
@@ -22,7 +22,7 @@ impl MovingSphere {
     time0: f32,
     time1: f32,
     radius: f32,
-    mtl: Arc<Material>,
+    mtl: Arc<dyn Material>,
   ) -> MovingSphere {
     MovingSphere {
       center0,
@@ -57,7 +57,10 @@ impl Hitable for MovingSphere {
         let n = (p - self.center(r.time)) / self.radius;
         let (u, v) = get_sphere_uv(p);
 
-        return Some(HitRecord::new(temp, p, n, self.mtl.clone(), u, v));
+        return Some(
+          HitRecord::new(temp, p, n, self.mtl.clone(), u, v)
+            .with_tangent(get_sphere_tangent(n)),
+        );
       }
 
       let temp = (-b + (b * b - a * c).sqrt()) / a;
@@ -66,7 +69,10 @@ impl Hitable for MovingSphere {
         let n = (p - self.center(r.time)) / self.radius;
         let (u, v) = get_sphere_uv(p);
 
-        return Some(HitRecord::new(temp, p, n, self.mtl.clone(), u, v));
+        return Some(
+          HitRecord::new(temp, p, n, self.mtl.clone(), u, v)
+            .with_tangent(get_sphere_tangent(n)),
+        );
       }
     }
 
@@ -74,7 +80,10 @@ impl Hitable for MovingSphere {
   }
 
   fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
-    //        Some(Aabb::new(self.center))
-    None
+    let r = Vec3::same(self.radius);
+    let box0 = Aabb::new(self.center(t0) - r, self.center(t0) + r);
+    let box1 = Aabb::new(self.center(t1) - r, self.center(t1) + r);
+
+    Some(Aabb::merge(&box0, &box1))
   }
 }
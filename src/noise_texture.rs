@@ -1,27 +1,86 @@
 use super::perlin::PerlinNoise;
-use super::texture::Texture;
+use super::texture::{Texture, TexturePoint};
 use super::vec3::Vec3;
 
+/// Marble-like bands along z, wobbled by Perlin turbulence.
 pub struct NoiseTexture {
   noise: PerlinNoise,
   scale: f32,
 }
 
 impl NoiseTexture {
+  /// Bands `scale` per world unit, with the turbulence at the same
+  /// frequency. The noise is seeded with 0 so renders repeat; use
+  /// `with_seed` for a different pattern.
   pub fn new(scale: f32) -> NoiseTexture {
     NoiseTexture {
-      noise: PerlinNoise::new(),
+      noise: PerlinNoise::with_seed(0),
       scale,
     }
   }
+
+  pub fn with_seed(mut self, seed: u64) -> NoiseTexture {
+    self.noise = PerlinNoise::with_seed(seed);
+    self
+  }
 }
 
 impl Texture for NoiseTexture {
-  fn value(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
-    //    Vec3::same(1_f32) * self.noise.noise(self.scale * p)
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    let p = self.scale * at.p;
     Vec3::same(1_f32)
       * 0.5_f32
-      * (1_f32
-        + (self.scale * p.z + 10_f32 * self.noise.turbulence(p, 7)).sin())
+      * (1_f32 + (p.z + 10_f32 * self.noise.turbulence(p, 7)).sin())
+  }
+}
+
+/// Points spread over a few units of space, for checking noise textures.
+#[cfg(test)]
+pub fn sample_points() -> Vec<Vec3> {
+  (0..200)
+    .map(|i| {
+      let t = i as f32 * 0.173_f32;
+      Vec3::new(t.sin() * 3_f32, t * 0.1_f32, (0.7_f32 * t).cos() * 2_f32)
+    })
+    .collect()
+}
+
+/// Checks that the textures `preset` builds from a seed stay within `lo`
+/// and `hi` per channel, and that the seed alone decides the pattern.
+#[cfg(test)]
+pub fn check_preset<T, F>(preset: F, lo: Vec3, hi: Vec3)
+where
+  T: Texture,
+  F: Fn(u64) -> T,
+{
+  let (a, b, c) = (preset(5), preset(5), preset(6));
+  let points = sample_points();
+  for &p in points.iter() {
+    let v = a.value(0_f32, 0_f32, p);
+    for i in 0..3 {
+      let within = lo[i] - 1e-5_f32 <= v[i] && v[i] <= hi[i] + 1e-5_f32;
+      assert!(within, "{:?}", v);
+    }
+    assert_eq!(v, b.value(0_f32, 0_f32, p));
+  }
+  assert!(points
+    .iter()
+    .any(|&p| a.value(0_f32, 0_f32, p) != c.value(0_f32, 0_f32, p)));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_noise_texture_is_seeded() {
+    let p = Vec3::new(0.3_f32, -1.2_f32, 2.7_f32);
+    let a = NoiseTexture::new(4_f32).with_seed(9);
+    let b = NoiseTexture::new(4_f32).with_seed(9);
+    assert_eq!(a.value(0_f32, 0_f32, p), b.value(0_f32, 0_f32, p));
+    assert_eq!(
+      NoiseTexture::new(4_f32).value(0_f32, 0_f32, p),
+      NoiseTexture::new(4_f32).value(0_f32, 0_f32, p)
+    );
   }
 }
@@ -0,0 +1,59 @@
+use super::bump_map::scatter_with_shading_normal;
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::texture::Texture;
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+/// Tangent space normal mapping: wraps a material and replaces its shading
+/// normal with one read from a texture, usually an `ImageTexture`. Red is
+/// along the tangent, green along the bitangent and blue along the normal,
+/// each mapped from [0, 1] to [-1, 1].
+pub struct NormalMap {
+  pub base: Arc<dyn Material>,
+  pub map: Arc<dyn Texture>,
+  /// Scales the tilt away from the surface normal; 1 is as authored.
+  pub strength: f32,
+}
+
+impl NormalMap {
+  pub fn new(
+    base: Arc<dyn Material>,
+    map: Arc<dyn Texture>,
+    strength: f32,
+  ) -> NormalMap {
+    NormalMap {
+      base,
+      map,
+      strength,
+    }
+  }
+
+  fn shading_normal(&self, r: &Ray, h: &HitRecord) -> Vec3 {
+    let c = 2_f32 * self.map.value_at(r, h) - Vec3::same(1_f32);
+    let frame = Onb::from_w_u(h.normal, h.tangent);
+
+    frame.to_world(Vec3::new(
+      self.strength * c.x,
+      self.strength * c.y,
+      c.z.max(0_f32),
+    ))
+  }
+}
+
+impl Material for NormalMap {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    scatter_with_shading_normal(
+      self.base.as_ref(),
+      r,
+      h,
+      self.shading_normal(r, h),
+    )
+  }
+
+  fn emitted(&self, r: &Ray, h: &HitRecord) -> Vec3 {
+    self.base.emitted(r, h)
+  }
+}
@@ -0,0 +1,54 @@
+use super::vec3::{cross_product, dot_product, unit_vector, Vec3};
+
+/// Orthonormal basis. `w` is the surface normal, `u` and `v` span the
+/// tangent plane.
+#[derive(Copy, Clone, Debug)]
+pub struct Onb {
+  pub u: Vec3,
+  pub v: Vec3,
+  pub w: Vec3,
+}
+
+impl Onb {
+  pub fn from_w(n: Vec3) -> Onb {
+    let w = unit_vector(n);
+    let a = if w.x.abs() > 0.9_f32 {
+      Vec3::new(0_f32, 1_f32, 0_f32)
+    } else {
+      Vec3::new(1_f32, 0_f32, 0_f32)
+    };
+    let v = unit_vector(cross_product(w, a));
+    let u = cross_product(v, w);
+
+    Onb { u, v, w }
+  }
+
+  /// Basis around the normal `n` with `u` along `tangent` projected onto
+  /// the tangent plane.
+  pub fn from_w_u(n: Vec3, tangent: Vec3) -> Onb {
+    let w = unit_vector(n);
+    let t = tangent - dot_product(tangent, w) * w;
+    if t.squared_length() < 1e-12_f32 {
+      return Onb::from_w(n);
+    }
+
+    let u = unit_vector(t);
+    let v = cross_product(w, u);
+
+    Onb { u, v, w }
+  }
+
+  /// Transforms a vector expressed in this basis to world space.
+  pub fn to_world(self, a: Vec3) -> Vec3 {
+    a.x * self.u + a.y * self.v + a.z * self.w
+  }
+
+  /// Transforms a world space vector into this basis.
+  pub fn to_local(self, a: Vec3) -> Vec3 {
+    Vec3::new(
+      dot_product(a, self.u),
+      dot_product(a, self.v),
+      dot_product(a, self.w),
+    )
+  }
+}
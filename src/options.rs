@@ -0,0 +1,201 @@
+use super::filter::FilterKind;
+use super::renderer::TileSchedule;
+use super::tile_scheduler::TileOrder;
+use std::str::FromStr;
+use std::time::Duration;
+
+pub const USAGE: &str = "usage: raytracer [options]
+  --width <pixels>            image width (default 1200)
+  --height <pixels>           image height (default 800)
+  --spp <samples>             samples per pixel (default 128)
+  --output <file.png>         output image (default raytraced.png)
+  --scene <name>              cornell_box, random_world, random_world_bvh,
+                              two_spheres, two_perlin_spheres, simple_light,
+                              metals, frosted_glass, tinted_glass, principled,
+                              layered, emitters, rough_diffuse,
+                              iridescent, brushed_metal, cloth, subsurface,
+                              bump_mapping, cutouts, fractal_noise,
+                              cellular, wood_and_stone,
+                              uv_textures, texture_graph,
+                              texture_filtering, triplanar
+  --filter <name>             box, tent, gaussian, mitchell, lanczos
+  --filter-radius <pixels>    reconstruction filter radius
+  --progressive               render in passes of increasing sample counts
+  --time-budget <seconds>     stop a progressive render after this long
+  --preview-interval <secs>   rewrite the output every so many seconds
+  --preview-passes <passes>   rewrite the output every so many passes
+  --seed <n>                  seed for the pixel sample positions
+  --scene-seed <n>            seed for randomly generated scenes
+  --checkpoint <file>         periodically save the render state here
+  --checkpoint-interval <s>   seconds between checkpoints (default 60)
+  --resume <file>             continue a checkpointed render up to --spp
+  --threads <n>               worker threads (default: available cores)
+  --tile-size <pixels>        edge length of a work tile (default 32)
+  --tile-order <order>        spiral (default), hilbert or scanline";
+
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+  pub width: u32,
+  pub height: u32,
+  pub spp: u32,
+  pub output: String,
+  pub scene: String,
+  pub filter: FilterKind,
+  pub filter_radius: Option<f32>,
+  pub progressive: bool,
+  pub time_budget: Option<Duration>,
+  pub preview_interval: Option<Duration>,
+  pub preview_passes: Option<u32>,
+  pub seed: u64,
+  pub scene_seed: u64,
+  pub checkpoint: Option<String>,
+  pub checkpoint_interval: Duration,
+  pub resume: Option<String>,
+  pub threads: usize,
+  pub tile_size: u32,
+  pub tile_order: TileOrder,
+}
+
+impl Default for RenderOptions {
+  fn default() -> RenderOptions {
+    let schedule = TileSchedule::default();
+
+    RenderOptions {
+      width: 1200,
+      height: 800,
+      spp: 128,
+      output: "raytraced.png".to_string(),
+      scene: "cornell_box".to_string(),
+      filter: FilterKind::Mitchell,
+      filter_radius: None,
+      progressive: false,
+      time_budget: None,
+      preview_interval: None,
+      preview_passes: None,
+      seed: 0,
+      scene_seed: 0,
+      checkpoint: None,
+      checkpoint_interval: Duration::from_secs(60),
+      resume: None,
+      threads: schedule.threads,
+      tile_size: schedule.tile_size,
+      tile_order: schedule.order,
+    }
+  }
+}
+
+fn parse_value<T: FromStr>(
+  name: &str,
+  value: Option<String>,
+) -> Result<T, String> {
+  let value = value.ok_or_else(|| format!("missing value for {}", name))?;
+  value
+    .parse::<T>()
+    .map_err(|_| format!("invalid value \"{}\" for {}", value, name))
+}
+
+fn parse_seconds(name: &str, value: Option<String>) -> Result<Duration, String> {
+  let secs = parse_value::<f32>(name, value)?;
+  if secs < 0_f32 {
+    return Err(format!("{} must not be negative", name));
+  }
+
+  Ok(Duration::from_millis((secs * 1000_f32) as u64))
+}
+
+fn parse_radius(name: &str, value: Option<String>) -> Result<f32, String> {
+  let radius = parse_value::<f32>(name, value)?;
+  if !(radius.is_finite() && radius > 0_f32) {
+    return Err(format!("{} must be a positive number", name));
+  }
+
+  Ok(radius)
+}
+
+impl RenderOptions {
+  pub fn from_args<I>(args: I) -> Result<RenderOptions, String>
+  where
+    I: IntoIterator<Item = String>,
+  {
+    let mut opts = RenderOptions::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+      match arg.as_str() {
+        "--width" => opts.width = parse_value(&arg, args.next())?,
+        "--height" => opts.height = parse_value(&arg, args.next())?,
+        "--spp" => opts.spp = parse_value(&arg, args.next())?,
+        "--output" => opts.output = parse_value(&arg, args.next())?,
+        "--scene" => opts.scene = parse_value(&arg, args.next())?,
+        "--filter" => opts.filter = parse_value(&arg, args.next())?,
+        "--filter-radius" => {
+          opts.filter_radius = Some(parse_radius(&arg, args.next())?)
+        }
+        "--progressive" => opts.progressive = true,
+        "--time-budget" => {
+          opts.time_budget = Some(parse_seconds(&arg, args.next())?)
+        }
+        "--preview-interval" => {
+          opts.preview_interval = Some(parse_seconds(&arg, args.next())?)
+        }
+        "--preview-passes" => {
+          opts.preview_passes = Some(parse_value(&arg, args.next())?)
+        }
+        "--seed" => opts.seed = parse_value(&arg, args.next())?,
+        "--scene-seed" => {
+          opts.scene_seed = parse_value(&arg, args.next())?
+        }
+        "--checkpoint" => {
+          opts.checkpoint = Some(parse_value(&arg, args.next())?)
+        }
+        "--checkpoint-interval" => {
+          opts.checkpoint_interval = parse_seconds(&arg, args.next())?
+        }
+        "--resume" => opts.resume = Some(parse_value(&arg, args.next())?),
+        "--threads" => opts.threads = parse_value(&arg, args.next())?,
+        "--tile-size" => opts.tile_size = parse_value(&arg, args.next())?,
+        "--tile-order" => opts.tile_order = parse_value(&arg, args.next())?,
+        _ => return Err(format!("unknown option {}", arg)),
+      }
+    }
+
+    if opts.width == 0 || opts.height == 0 || opts.spp == 0 {
+      return Err("width, height and spp must be positive".to_string());
+    }
+
+    if opts.threads == 0 || opts.tile_size == 0 {
+      return Err("threads and tile size must be positive".to_string());
+    }
+
+    if opts.preview_passes == Some(0) {
+      return Err("--preview-passes must be positive".to_string());
+    }
+
+    Ok(opts)
+  }
+
+  pub fn filter_radius(&self) -> f32 {
+    self
+      .filter_radius
+      .unwrap_or_else(|| self.filter.default_radius())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &[&str]) -> Result<RenderOptions, String> {
+    RenderOptions::from_args(args.iter().map(|a| a.to_string()))
+  }
+
+  #[test]
+  fn test_filter_radius_must_be_positive() {
+    let radius =
+      |r: &str| parse(&["--filter-radius", r]).map(|o| o.filter_radius);
+    assert_eq!(radius("1.5"), Ok(Some(1.5_f32)));
+    for bad in &["0", "-1", "NaN", "inf"] {
+      assert!(radius(bad).is_err(), "{} was accepted", bad);
+    }
+  }
+}
@@ -0,0 +1,98 @@
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::texture::Texture;
+use super::vec3::{dot_product, random_cosine_direction, unit_vector, Vec3};
+use std::sync::Arc;
+
+/// Rough diffuse reflection from V-shaped microfacets (Oren and Nayar,
+/// "Generalization of Lambert's Reflectance Model", 1994), in the
+/// qualitative form used by pbrt. Backscatters more than `Lambertian`,
+/// which is what makes clay or the full Moon look flat.
+pub struct OrenNayar {
+  pub albedo: Arc<dyn Texture>,
+  a: f32,
+  b: f32,
+}
+
+impl OrenNayar {
+  /// `sigma` is the standard deviation of the facet angles in degrees, 0
+  /// is Lambertian.
+  pub fn new(albedo: Arc<dyn Texture>, sigma: f32) -> OrenNayar {
+    let sigma = sigma.to_radians();
+    let sigma2 = sigma * sigma;
+
+    OrenNayar {
+      albedo,
+      a: 1_f32 - sigma2 / (2_f32 * (sigma2 + 0.33_f32)),
+      b: 0.45_f32 * sigma2 / (sigma2 + 0.09_f32),
+    }
+  }
+
+  /// The bracketed factor of the model, for local directions on the same
+  /// side as the normal.
+  fn factor(&self, wo: Vec3, wi: Vec3) -> f32 {
+    let sin_o = (1_f32 - wo.z * wo.z).max(0_f32).sqrt();
+    let sin_i = (1_f32 - wi.z * wi.z).max(0_f32).sqrt();
+
+    let max_cos = if sin_o > 1e-4_f32 && sin_i > 1e-4_f32 {
+      ((wi.x * wo.x + wi.y * wo.y) / (sin_i * sin_o)).max(0_f32)
+    } else {
+      0_f32
+    };
+
+    //
+    // sin(alpha) tan(beta), with alpha the larger of the two angles to the
+    // normal and beta the smaller.
+    let (sin_alpha, tan_beta) = if wi.z.abs() > wo.z.abs() {
+      (sin_o, sin_i / wi.z.abs())
+    } else {
+      (sin_i, sin_o / wo.z.abs())
+    };
+
+    self.a + self.b * max_cos * sin_alpha * tan_beta
+  }
+}
+
+impl Material for OrenNayar {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    let n = if dot_product(r.direction, h.normal) < 0_f32 {
+      h.normal
+    } else {
+      -h.normal
+    };
+
+    let frame = Onb::from_w(n);
+    let wo = frame.to_local(-unit_vector(r.direction));
+    let wi = random_cosine_direction();
+
+    //
+    // Cosine sampling cancels the cosine and the 1 / pi of the BRDF.
+    let attenuation = self.albedo.value_at(r, h) * self.factor(wo, wi);
+
+    Some((attenuation, Ray::new(h.p, frame.to_world(wi), r.time)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+
+  #[test]
+  fn test_factor() {
+    let albedo = Arc::new(ConstantTexture::same(1_f32));
+    let wo = unit_vector(Vec3::new(0.6_f32, 0_f32, 0.8_f32));
+    let wi = unit_vector(Vec3::new(0.8_f32, 0_f32, 0.6_f32));
+
+    let smooth = OrenNayar::new(albedo.clone(), 0_f32);
+    assert!((smooth.factor(wo, wi) - 1_f32).abs() < 1e-6_f32);
+
+    //
+    // Rough surfaces reflect more back towards the light than forward.
+    let rough = OrenNayar::new(albedo, 30_f32);
+    let forward = unit_vector(Vec3::new(-0.8_f32, 0_f32, 0.6_f32));
+    assert!(rough.factor(wo, wi) > rough.factor(wo, forward));
+  }
+}
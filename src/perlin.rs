@@ -8,10 +8,9 @@ pub struct PerlinNoise {
   perm_z: Vec<i32>,
 }
 
-fn gen_shuffled_vec(elements: u32) -> Vec<i32> {
+fn gen_shuffled_vec(elements: u32, rng: &mut StdRng) -> Vec<i32> {
   let mut v = (0..elements).map(|i| i as i32).collect::<Vec<_>>();
-  let mut rng = thread_rng();
-  rng.shuffle(&mut v);
+  v.shuffle(rng);
 
   v
 }
@@ -59,8 +58,14 @@ fn smooth(t: f32) -> f32 {
 }
 
 impl PerlinNoise {
+  /// Noise with a random table, different on every run.
   pub fn new() -> PerlinNoise {
-    let mut rng = thread_rng();
+    PerlinNoise::with_seed(thread_rng().gen())
+  }
+
+  /// Noise whose table is fixed by `seed`, so renders can be repeated.
+  pub fn with_seed(seed: u64) -> PerlinNoise {
+    let mut rng = StdRng::seed_from_u64(seed);
 
     PerlinNoise {
       randfloat: (0..256)
@@ -72,9 +77,9 @@ impl PerlinNoise {
           ))
         })
         .collect::<Vec<_>>(),
-      perm_x: gen_shuffled_vec(256),
-      perm_y: gen_shuffled_vec(256),
-      perm_z: gen_shuffled_vec(256),
+      perm_x: gen_shuffled_vec(256, &mut rng),
+      perm_y: gen_shuffled_vec(256, &mut rng),
+      perm_z: gen_shuffled_vec(256, &mut rng),
     }
   }
 
@@ -0,0 +1,380 @@
+use super::constant_texture::ConstantTexture;
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::microfacet::GgxDistribution;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::rough_dielectric::sample_rough_interface;
+use super::texture::Texture;
+use super::vec3::{
+  dot_product, random_cosine_direction, reflect, unit_vector, Vec3,
+};
+use rand::prelude::*;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// How much the sheen takes on the hue of the base colour.
+const SHEEN_TINT: f32 = 0.5_f32;
+
+/// Roughness of the masking term on the clear coat, fixed as in Disney's
+/// model.
+const CLEARCOAT_MASKING_ALPHA: f32 = 0.25_f32;
+
+/// The Disney principled BSDF (Burley, "Physically Based Shading at
+/// Disney", 2012, with the transmission lobe from the 2015 notes). Every
+/// parameter is a texture; scalar parameters are read as a single channel
+/// in [0, 1], except `ior`.
+pub struct Principled {
+  pub base_color: Arc<dyn Texture>,
+  pub metallic: Arc<dyn Texture>,
+  pub roughness: Arc<dyn Texture>,
+  /// Specular reflectance of the dielectric part, 0.5 is 4%.
+  pub specular: Arc<dyn Texture>,
+  /// Tints the dielectric specular towards the base colour.
+  pub specular_tint: Arc<dyn Texture>,
+  pub sheen: Arc<dyn Texture>,
+  pub clearcoat: Arc<dyn Texture>,
+  /// 0 gives a satin clear coat, 1 a glossy one.
+  pub clearcoat_gloss: Arc<dyn Texture>,
+  /// Fraction of the dielectric part that is glass rather than opaque.
+  pub transmission: Arc<dyn Texture>,
+  pub ior: Arc<dyn Texture>,
+}
+
+/// The parameters evaluated at a hit point.
+struct Parameters {
+  base_color: Vec3,
+  metallic: f32,
+  roughness: f32,
+  specular: f32,
+  specular_tint: f32,
+  sheen: f32,
+  clearcoat: f32,
+  clearcoat_gloss: f32,
+  transmission: f32,
+  ior: f32,
+}
+
+enum Lobe {
+  Diffuse,
+  Specular,
+  Clearcoat,
+}
+
+impl Principled {
+  /// A plastic-like material with the Disney defaults for everything but
+  /// the base colour.
+  pub fn new(base_color: Arc<dyn Texture>) -> Principled {
+    Principled {
+      base_color,
+      metallic: Arc::new(ConstantTexture::same(0_f32)),
+      roughness: Arc::new(ConstantTexture::same(0.5_f32)),
+      specular: Arc::new(ConstantTexture::same(0.5_f32)),
+      specular_tint: Arc::new(ConstantTexture::same(0_f32)),
+      sheen: Arc::new(ConstantTexture::same(0_f32)),
+      clearcoat: Arc::new(ConstantTexture::same(0_f32)),
+      clearcoat_gloss: Arc::new(ConstantTexture::same(1_f32)),
+      transmission: Arc::new(ConstantTexture::same(0_f32)),
+      ior: Arc::new(ConstantTexture::same(1.5_f32)),
+    }
+  }
+
+  pub fn with_metallic(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.metallic = t;
+    self
+  }
+
+  pub fn with_roughness(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.roughness = t;
+    self
+  }
+
+  pub fn with_specular(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.specular = t;
+    self
+  }
+
+  pub fn with_specular_tint(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.specular_tint = t;
+    self
+  }
+
+  pub fn with_sheen(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.sheen = t;
+    self
+  }
+
+  pub fn with_clearcoat(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.clearcoat = t;
+    self
+  }
+
+  pub fn with_clearcoat_gloss(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.clearcoat_gloss = t;
+    self
+  }
+
+  pub fn with_transmission(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.transmission = t;
+    self
+  }
+
+  pub fn with_ior(mut self, t: Arc<dyn Texture>) -> Principled {
+    self.ior = t;
+    self
+  }
+
+  fn evaluate(&self, r: &Ray, h: &HitRecord) -> Parameters {
+    let unit = |t: &Arc<dyn Texture>| t.scalar_at(r, h).clamp(0_f32, 1_f32);
+
+    Parameters {
+      base_color: self.base_color.value_at(r, h),
+      metallic: unit(&self.metallic),
+      roughness: unit(&self.roughness),
+      specular: unit(&self.specular),
+      specular_tint: unit(&self.specular_tint),
+      sheen: unit(&self.sheen),
+      clearcoat: unit(&self.clearcoat),
+      clearcoat_gloss: unit(&self.clearcoat_gloss),
+      transmission: unit(&self.transmission),
+      ior: self.ior.scalar_at(r, h).max(1e-3_f32),
+    }
+  }
+}
+
+fn luminance(c: Vec3) -> f32 {
+  0.2126_f32 * c.x + 0.7152_f32 * c.y + 0.0722_f32 * c.z
+}
+
+fn mix(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+  (1_f32 - t) * a + t * b
+}
+
+fn schlick_weight(cosine: f32) -> f32 {
+  (1_f32 - cosine).clamp(0_f32, 1_f32).powi(5)
+}
+
+/// Berry's distribution (GTR with gamma = 1), used for the clear coat.
+fn gtr1(cos_h: f32, alpha: f32) -> f32 {
+  let a2 = alpha * alpha;
+  let t = 1_f32 + (a2 - 1_f32) * cos_h * cos_h;
+  (a2 - 1_f32) / (PI * a2.ln() * t)
+}
+
+fn sample_gtr1(alpha: f32, u1: f32, u2: f32) -> Vec3 {
+  let a2 = alpha * alpha;
+  let cos_theta = ((1_f32 - a2.powf(1_f32 - u1)) / (1_f32 - a2))
+    .clamp(0_f32, 1_f32)
+    .sqrt();
+  let sin_theta = (1_f32 - cos_theta * cos_theta).sqrt();
+  let phi = 2_f32 * PI * u2;
+
+  Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+impl Principled {
+  /// The glass lobe: a rough dielectric interface whose refracted light is
+  /// tinted by the square root of the base colour, so that passing in and
+  /// out of an object tints it by the base colour.
+  fn scatter_glass(
+    &self,
+    r: &Ray,
+    h: &HitRecord,
+    params: &Parameters,
+  ) -> Option<(Vec3, Ray)> {
+    let distribution = GgxDistribution::isotropic(
+      GgxDistribution::roughness_to_alpha(params.roughness),
+    );
+    let (direction, weight, refracted) =
+      sample_rough_interface(r.direction, h.normal, params.ior, &distribution)?;
+
+    let tint = if refracted {
+      let c = params.base_color;
+      Vec3::new(c.x.sqrt(), c.y.sqrt(), c.z.sqrt())
+    } else {
+      Vec3::same(1_f32)
+    };
+
+    Some((tint * weight, Ray::new(h.p, direction, r.time)))
+  }
+
+  /// The opaque lobes: diffuse with retro-reflection and sheen, GGX
+  /// specular and the clear coat. One lobe is picked in proportion to its
+  /// rough albedo and the sample weighted against the pdf of all three.
+  fn scatter_opaque(
+    &self,
+    r: &Ray,
+    h: &HitRecord,
+    params: &Parameters,
+  ) -> Option<(Vec3, Ray)> {
+    let frame = Onb::from_w(h.normal);
+    let wo = frame.to_local(-unit_vector(r.direction));
+    if wo.z <= 0_f32 {
+      return None;
+    }
+
+    let base = params.base_color;
+    let dielectric = 1_f32 - params.metallic;
+    let lum = luminance(base);
+    let tint = if lum > 0_f32 {
+      base / lum
+    } else {
+      Vec3::same(1_f32)
+    };
+    let spec0 = mix(
+      params.specular
+        * 0.08_f32
+        * mix(Vec3::same(1_f32), tint, params.specular_tint),
+      base,
+      params.metallic,
+    );
+    let sheen_color = mix(Vec3::same(1_f32), tint, SHEEN_TINT);
+
+    let specular = GgxDistribution::isotropic(
+      GgxDistribution::roughness_to_alpha(params.roughness),
+    );
+    let clearcoat_alpha =
+      0.1_f32 + (0.001_f32 - 0.1_f32) * params.clearcoat_gloss;
+    let clearcoat_masking = GgxDistribution::isotropic(CLEARCOAT_MASKING_ALPHA);
+
+    let fv = schlick_weight(wo.z);
+    let p_diffuse = dielectric * (lum + 0.25_f32 * params.sheen);
+    let p_specular = luminance(mix(spec0, Vec3::same(1_f32), fv));
+    let p_clearcoat = 0.25_f32 * params.clearcoat * (0.04_f32 + 0.96_f32 * fv);
+    let p_total = p_diffuse + p_specular + p_clearcoat;
+    if p_total <= 0_f32 {
+      return None;
+    }
+    let (p_diffuse, p_specular, p_clearcoat) = (
+      p_diffuse / p_total,
+      p_specular / p_total,
+      p_clearcoat / p_total,
+    );
+
+    let mut rng = thread_rng();
+    let u: f32 = rng.gen();
+    let lobe = if u < p_diffuse {
+      Lobe::Diffuse
+    } else if u < p_diffuse + p_specular {
+      Lobe::Specular
+    } else {
+      Lobe::Clearcoat
+    };
+
+    let wi = match lobe {
+      Lobe::Diffuse => random_cosine_direction(),
+      Lobe::Specular => {
+        let m = specular.sample_visible_normal(wo, rng.gen(), rng.gen());
+        reflect(-wo, m)
+      }
+      Lobe::Clearcoat => {
+        let m = sample_gtr1(clearcoat_alpha, rng.gen(), rng.gen());
+        reflect(-wo, m)
+      }
+    };
+    if wi.z <= 0_f32 {
+      return None;
+    }
+
+    let m = unit_vector(wo + wi);
+    let cos_d = dot_product(wi, m);
+    let fl = schlick_weight(wi.z);
+    let fh = schlick_weight(cos_d);
+
+    let fd90 = 0.5_f32 + 2_f32 * cos_d * cos_d * params.roughness;
+    let diffuse = base
+      * ((1_f32 + (fd90 - 1_f32) * fl) * (1_f32 + (fd90 - 1_f32) * fv) / PI);
+    let sheen = fh * params.sheen * sheen_color;
+
+    let spec = mix(spec0, Vec3::same(1_f32), fh)
+      * (specular.d(m) * specular.g2(wo, wi) / (4_f32 * wo.z * wi.z));
+
+    let clearcoat = 0.25_f32
+      * params.clearcoat
+      * (0.04_f32 + 0.96_f32 * fh)
+      * gtr1(m.z, clearcoat_alpha)
+      * clearcoat_masking.g1(wo)
+      * clearcoat_masking.g1(wi)
+      / (4_f32 * wo.z * wi.z);
+
+    let f = dielectric * (diffuse + sheen) + spec + Vec3::same(clearcoat);
+
+    let pdf = p_diffuse * wi.z / PI
+      + p_specular * specular.visible_normal_pdf(wo, m) / (4_f32 * cos_d)
+      + p_clearcoat * gtr1(m.z, clearcoat_alpha) * m.z / (4_f32 * cos_d);
+    if pdf <= 0_f32 {
+      return None;
+    }
+
+    Some((f * (wi.z / pdf), Ray::new(h.p, frame.to_world(wi), r.time)))
+  }
+}
+
+impl Material for Principled {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    let params = self.evaluate(r, h);
+
+    //
+    // A ray inside the surface can only have got there through the glass
+    // lobe, so it has to leave through it too. Outside, the glass lobe is
+    // picked with its weight, which cancels out of the estimate.
+    let inside = dot_product(r.direction, h.normal) > 0_f32;
+    let glass = (1_f32 - params.metallic) * params.transmission;
+    if inside || thread_rng().gen::<f32>() < glass {
+      self.scatter_glass(r, h, &params)
+    } else {
+      self.scatter_opaque(r, h, &params)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn mean_weight(mtl: Principled, samples: usize) -> Vec3 {
+    let mtl = Arc::new(mtl);
+    let h = HitRecord::new(
+      1_f32,
+      Vec3::same(0_f32),
+      Vec3::new(0_f32, 0_f32, 1_f32),
+      mtl.clone(),
+      0_f32,
+      0_f32,
+    );
+    let r = Ray::new(
+      Vec3::new(0_f32, 0_f32, 1_f32),
+      Vec3::new(0_f32, 0_f32, -1_f32),
+      0_f32,
+    );
+
+    let mut sum = Vec3::same(0_f32);
+    for _ in 0..samples {
+      if let Some((attenuation, _)) = mtl.scatter(&r, &h) {
+        sum += attenuation;
+      }
+    }
+    sum / samples as f32
+  }
+
+  #[test]
+  fn test_white_furnace() {
+    let white = || Arc::new(ConstantTexture::same(1_f32));
+
+    //
+    // A white metal loses only a little energy to single scattering.
+    let metal = Principled::new(white())
+      .with_metallic(white())
+      .with_roughness(Arc::new(ConstantTexture::same(0.5_f32)));
+    let albedo = mean_weight(metal, 20000);
+    assert!(albedo.x > 0.85_f32 && albedo.x < 1.01_f32, "{:?}", albedo);
+
+    //
+    // Clear glass at normal incidence neither gains nor loses energy.
+    let glass = Principled::new(white())
+      .with_transmission(white())
+      .with_roughness(Arc::new(ConstantTexture::same(0.2_f32)));
+    let albedo = mean_weight(glass, 20000);
+    assert!(albedo.y > 0.9_f32 && albedo.y < 1.01_f32, "{:?}", albedo);
+  }
+}
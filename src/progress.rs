@@ -0,0 +1,113 @@
+use std::io::Write;
+use std::time::Duration;
+
+/// Snapshot of a running render, handed to the renderer's progress
+/// callback.
+#[derive(Copy, Clone, Debug)]
+pub struct Progress {
+  /// Number of the pass being rendered, starting at 1.
+  pub pass: u32,
+  pub tiles_done: usize,
+  pub tiles_total: usize,
+  /// Fraction of the whole render done so far, across all passes.
+  pub fraction: f32,
+  pub elapsed: Duration,
+  /// Estimated time left, once enough work is done to tell.
+  pub eta: Option<Duration>,
+  pub rays_per_second: f32,
+}
+
+fn format_duration(d: Duration) -> String {
+  let secs = d.as_secs();
+  if secs >= 3600 {
+    format!("{}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+  } else {
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+  }
+}
+
+fn format_rate(rays_per_second: f32) -> String {
+  if rays_per_second >= 1e6_f32 {
+    format!("{:.1} Mrays/s", rays_per_second / 1e6_f32)
+  } else {
+    format!("{:.1} Krays/s", rays_per_second / 1e3_f32)
+  }
+}
+
+/// Single line terminal progress bar, redrawn in place on stderr.
+pub struct ProgressBar {
+  width: usize,
+  last_len: usize,
+}
+
+impl ProgressBar {
+  pub fn new(width: usize) -> ProgressBar {
+    ProgressBar { width, last_len: 0 }
+  }
+
+  pub fn format(&self, p: &Progress) -> String {
+    let filled = ((p.fraction.clamp(0_f32, 1_f32) * self.width as f32)
+      as usize)
+      .min(self.width);
+    let eta = p
+      .eta
+      .map(format_duration)
+      .unwrap_or_else(|| "--:--".to_string());
+
+    format!(
+      "[{}{}] {:>3}% pass {} tile {}/{} | {} elapsed | ETA {} | {}",
+      "#".repeat(filled),
+      ".".repeat(self.width - filled),
+      (p.fraction * 100_f32) as u32,
+      p.pass,
+      p.tiles_done,
+      p.tiles_total,
+      format_duration(p.elapsed),
+      eta,
+      format_rate(p.rays_per_second)
+    )
+  }
+
+  pub fn update(&mut self, p: &Progress) {
+    let line = self.format(p);
+    let padding = self.last_len.saturating_sub(line.len());
+    self.last_len = line.len();
+
+    let mut stderr = std::io::stderr();
+    let _ = write!(stderr, "\r{}{}", line, " ".repeat(padding));
+    let _ = stderr.flush();
+  }
+
+  /// Moves past the bar so that further output starts on a fresh line.
+  pub fn finish(&mut self) {
+    if self.last_len > 0 {
+      eprintln!();
+      self.last_len = 0;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_progress_bar_format() {
+    let bar = ProgressBar::new(10);
+    let line = bar.format(&Progress {
+      pass: 2,
+      tiles_done: 3,
+      tiles_total: 12,
+      fraction: 0.5_f32,
+      elapsed: Duration::from_secs(75),
+      eta: Some(Duration::from_secs(3725)),
+      rays_per_second: 2.5e6_f32,
+    });
+
+    assert_eq!(
+      line,
+      "[#####.....]  50% pass 2 tile 3/12 | 01:15 elapsed | ETA 1:02:05 \
+       | 2.5 Mrays/s"
+    );
+  }
+}
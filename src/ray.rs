@@ -1,3 +1,4 @@
+use super::ray_differential::RayDifferential;
 use super::vec3::Vec3;
 
 #[derive(Copy, Clone, Debug)]
@@ -5,6 +6,9 @@ pub struct Ray {
   pub origin: Vec3,
   pub direction: Vec3,
   pub time: f32,
+  /// Neighbouring rays for texture filtering. Camera rays have them and
+  /// mirror and glass bounces keep them; other bounces drop them.
+  pub differential: Option<RayDifferential>,
 }
 
 impl Ray {
@@ -13,9 +17,15 @@ impl Ray {
       origin,
       direction,
       time,
+      differential: None,
     }
   }
 
+  pub fn with_differential(mut self, d: Option<RayDifferential>) -> Ray {
+    self.differential = d;
+    self
+  }
+
   pub fn point_at_param(self, t: f32) -> Vec3 {
     self.origin + self.direction * t
   }
@@ -0,0 +1,171 @@
+use super::hitable::HitRecord;
+use super::ray::Ray;
+use super::vec3::{dot_product, reflect, refract, unit_vector, Vec3};
+
+/// Two rays offset from a main ray by one pixel sideways and one pixel up
+/// (Igehy, "Tracing Ray Differentials", 1999). Where they land on a
+/// surface tells how much of a texture a pixel covers.
+#[derive(Copy, Clone, Debug)]
+pub struct RayDifferential {
+  pub rx_origin: Vec3,
+  pub rx_direction: Vec3,
+  pub ry_origin: Vec3,
+  pub ry_direction: Vec3,
+}
+
+/// The area a pixel covers around a hit, as the change in position and
+/// texture coordinates from one pixel to the next.
+#[derive(Copy, Clone, Debug)]
+pub struct Footprint {
+  pub dpdx: Vec3,
+  pub dpdy: Vec3,
+  pub dudx: f32,
+  pub dvdx: f32,
+  pub dudy: f32,
+  pub dvdy: f32,
+}
+
+/// Change in the hit position from one pixel to the next, found by landing
+/// the offset rays on the tangent plane at the hit.
+pub fn position_differentials(r: &Ray, h: &HitRecord) -> Option<(Vec3, Vec3)> {
+  let d = r.differential?;
+
+  let n = h.geometric_normal;
+  let plane = dot_product(n, h.p);
+  let on_plane = |o: Vec3, dir: Vec3| {
+    let denom = dot_product(n, dir);
+    if denom.abs() < 1e-8_f32 {
+      return None;
+    }
+    Some(o + (plane - dot_product(n, o)) / denom * dir)
+  };
+
+  Some((
+    on_plane(d.rx_origin, d.rx_direction)? - h.p,
+    on_plane(d.ry_origin, d.ry_direction)? - h.p,
+  ))
+}
+
+/// Footprint of `r` at `h`, if the ray carries differentials and the
+/// surface knows how its position changes with (u, v).
+pub fn footprint(r: &Ray, h: &HitRecord) -> Option<Footprint> {
+  let (dpdx, dpdy) = position_differentials(r, h)?;
+
+  //
+  // Least squares solution of dpdx = dpdu dudx + dpdv dvdx, and the same
+  // for y.
+  let a00 = dot_product(h.dpdu, h.dpdu);
+  let a01 = dot_product(h.dpdu, h.dpdv);
+  let a11 = dot_product(h.dpdv, h.dpdv);
+  let det = a00 * a11 - a01 * a01;
+  if det.abs() < 1e-12_f32 {
+    return None;
+  }
+  let solve = |dp: Vec3| {
+    let b0 = dot_product(h.dpdu, dp);
+    let b1 = dot_product(h.dpdv, dp);
+    ((a11 * b0 - a01 * b1) / det, (a00 * b1 - a01 * b0) / det)
+  };
+  let (dudx, dvdx) = solve(dpdx);
+  let (dudy, dvdy) = solve(dpdy);
+
+  let finite = [dudx, dvdx, dudy, dvdy].iter().all(|d| d.is_finite());
+  if !finite {
+    return None;
+  }
+
+  Some(Footprint {
+    dpdx,
+    dpdy,
+    dudx,
+    dvdx,
+    dudy,
+    dvdy,
+  })
+}
+
+/// Shading normal at the hit of an offset ray, following the curvature of
+/// the surface.
+fn offset_normal(h: &HitRecord, du: f32, dv: f32) -> Vec3 {
+  unit_vector(h.normal + du * h.dndu + dv * h.dndv)
+}
+
+/// Differentials of a mirror reflection of `r` at `h`. The offset rays
+/// reflect off the surface one pixel away, so curved mirrors spread or
+/// focus the footprint.
+pub fn reflected(r: &Ray, h: &HitRecord) -> Option<RayDifferential> {
+  let d = r.differential?;
+  let f = footprint(r, h)?;
+
+  Some(RayDifferential {
+    rx_origin: h.p + f.dpdx,
+    rx_direction: reflect(d.rx_direction, offset_normal(h, f.dudx, f.dvdx)),
+    ry_origin: h.p + f.dpdy,
+    ry_direction: reflect(d.ry_direction, offset_normal(h, f.dudy, f.dvdy)),
+  })
+}
+
+/// Differentials of `r` refracted at `h`, with `ni_over_nt` the ratio of
+/// the index the ray comes from to the one it enters. None if an offset
+/// ray is totally internally reflected.
+pub fn refracted(
+  r: &Ray,
+  h: &HitRecord,
+  ni_over_nt: f32,
+) -> Option<RayDifferential> {
+  let d = r.differential?;
+  let f = footprint(r, h)?;
+
+  let bend = |dir: Vec3, n: Vec3| {
+    let n = if dot_product(dir, n) > 0_f32 { -n } else { n };
+    refract(dir, n, ni_over_nt)
+  };
+
+  Some(RayDifferential {
+    rx_origin: h.p + f.dpdx,
+    rx_direction: bend(d.rx_direction, offset_normal(h, f.dudx, f.dvdx))?,
+    ry_origin: h.p + f.dpdy,
+    ry_direction: bend(d.ry_direction, offset_normal(h, f.dudy, f.dvdy))?,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+  use crate::hitable::Hitable;
+  use crate::lambertian::Lambertian;
+  use crate::xy_rect::XYRect;
+  use std::sync::Arc;
+
+  #[test]
+  fn test_footprint_on_a_plane() {
+    let mtl =
+      Arc::new(Lambertian::new(Arc::new(ConstantTexture::same(0.5_f32))));
+    let rect = XYRect::new(0_f32, 4_f32, 0_f32, 2_f32, 0_f32, mtl);
+
+    //
+    // Parallel rays 0.1 apart looking straight down at a rect 4 wide and
+    // 2 high.
+    let origin = Vec3::new(1_f32, 1_f32, 1_f32);
+    let down = Vec3::new(0_f32, 0_f32, -1_f32);
+    let r =
+      Ray::new(origin, down, 0_f32).with_differential(Some(RayDifferential {
+        rx_origin: origin + Vec3::new(0.1_f32, 0_f32, 0_f32),
+        rx_direction: down,
+        ry_origin: origin + Vec3::new(0_f32, 0.1_f32, 0_f32),
+        ry_direction: down,
+      }));
+
+    let h = rect.hit(&r, 0_f32, 10_f32).unwrap();
+    let f = footprint(&r, &h).unwrap();
+    assert!((f.dudx - 0.025_f32).abs() < 1e-6_f32);
+    assert!((f.dvdy - 0.05_f32).abs() < 1e-6_f32);
+    assert!(f.dvdx.abs() < 1e-6_f32 && f.dudy.abs() < 1e-6_f32);
+
+    //
+    // A flat mirror keeps parallel rays parallel.
+    let d = reflected(&r, &h).unwrap();
+    assert!((d.rx_direction - Vec3::new(0_f32, 0_f32, 1_f32)).length() < 1e-6);
+  }
+}
@@ -0,0 +1,534 @@
+use super::camera::Camera;
+use super::film::{Film, FilmTile};
+use super::hitable::Hitable;
+use super::progress::Progress;
+use super::ray::Ray;
+use super::sampler::Sampler;
+use super::tile_scheduler::{generate_tiles, TileOrder, WorkQueue};
+use super::vec3::Vec3;
+use rand::Rng;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the thread driving a pass wakes up to check for cancellation
+/// and give the caller a chance to look at the film.
+const UPDATE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Upper bound on the samples per pixel taken in a single progressive pass,
+/// so previews keep coming at a steady rate on long renders.
+const MAX_PASS_SPP: u32 = 64;
+
+/// Surface bounces a path may take.
+const MAX_DEPTH: i32 = 50;
+
+/// Steps a path may take through media. A walk through a dense medium
+/// easily takes hundreds before it gets out again.
+const MAX_MEDIUM_STEPS: i32 = 1000;
+
+/// Radiance arriving along `r`. `depth` and `medium_steps` are the bounces
+/// and medium steps taken so far. `rays` is incremented for every ray
+/// traced.
+pub fn color(
+  r: &Ray,
+  world: &Arc<dyn Hitable>,
+  depth: i32,
+  medium_steps: i32,
+  rays: &mut u64,
+) -> Vec3 {
+  *rays += 1;
+
+  if let Some(hit) = world.hit(r, 0.001f32, f32::MAX) {
+    let emitted = hit.mtl.emitted(r, &hit);
+    let (depth, medium_steps) = if hit.mtl.is_medium_step(r, &hit) {
+      (depth, medium_steps + 1)
+    } else {
+      (depth + 1, medium_steps)
+    };
+
+    if depth <= MAX_DEPTH && medium_steps <= MAX_MEDIUM_STEPS {
+      if let Some((attn, scattered)) = hit.mtl.scatter(r, &hit) {
+        return emitted
+          + attn * color(&scattered, world, depth, medium_steps, rays);
+      }
+    }
+
+    return emitted;
+  }
+
+  Vec3::same(0_f32)
+
+  // let unit_direction = vec3::unit_vector(r.direction);
+  // let t = 0.5f32 * (unit_direction.y + 1f32);
+  // (1f32 - t) * Vec3::new(1f32, 1f32, 1f32) + t * Vec3::new(0.5f32, 0.7f32, 1f32)
+}
+
+/// How a pass is cut into tiles and spread over the worker threads.
+#[derive(Copy, Clone, Debug)]
+pub struct TileSchedule {
+  pub threads: usize,
+  pub tile_size: u32,
+  pub order: TileOrder,
+}
+
+impl Default for TileSchedule {
+  fn default() -> TileSchedule {
+    TileSchedule {
+      threads: thread::available_parallelism().map_or(4, |n| n.get()),
+      tile_size: 32,
+      order: TileOrder::Spiral,
+    }
+  }
+}
+
+pub type ProgressCallback = Box<dyn FnMut(&Progress)>;
+
+/// Progress bookkeeping for a render made of one or more passes.
+struct Job {
+  start: Instant,
+  pass: u32,
+  samples_total: u64,
+  samples_done: u64,
+  /// Fraction of a pixel each camera sample covers, the same for every
+  /// pass so the texture filtering doesn't change as samples accumulate.
+  footprint: f32,
+}
+
+/// With many samples in a pixel each only needs to cover part of it, so
+/// textures can stay sharper.
+fn sample_footprint(spp: u32) -> f32 {
+  (1_f32 / (spp.max(1) as f32).sqrt()).max(0.125_f32)
+}
+
+pub struct Renderer {
+  world: Arc<dyn Hitable>,
+  camera: Camera,
+  film: Arc<Mutex<Film>>,
+  sampler: Sampler,
+  schedule: TileSchedule,
+  cancel: Arc<AtomicBool>,
+  deadline: Option<Instant>,
+  job: Option<Job>,
+  rays: Arc<AtomicU64>,
+  progress_callback: Option<ProgressCallback>,
+}
+
+impl Renderer {
+  pub fn new(
+    world: Arc<dyn Hitable>,
+    camera: Camera,
+    film: Film,
+    sampler: Sampler,
+  ) -> Renderer {
+    Renderer {
+      world,
+      camera,
+      film: Arc::new(Mutex::new(film)),
+      sampler,
+      schedule: TileSchedule::default(),
+      cancel: Arc::new(AtomicBool::new(false)),
+      deadline: None,
+      job: None,
+      rays: Arc::new(AtomicU64::new(0)),
+      progress_callback: None,
+    }
+  }
+
+  /// Locks the film. Workers merge finished tiles into it while a pass is
+  /// running, so don't hold on to it for long.
+  pub fn film(&self) -> MutexGuard<'_, Film> {
+    self.film.lock().unwrap()
+  }
+
+  pub fn sampler(&self) -> Sampler {
+    self.sampler
+  }
+
+  pub fn set_schedule(&mut self, schedule: TileSchedule) {
+    self.schedule = schedule;
+  }
+
+  /// Flag that stops the render, dropping the tiles in flight. Setting it
+  /// from another thread (e.g. a Ctrl-C handler) is safe.
+  pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+    self.cancel.clone()
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancel.load(Ordering::SeqCst)
+  }
+
+  /// Cancels the render once `deadline` is reached.
+  pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+    self.deadline = deadline;
+  }
+
+  /// Registers a function that receives progress reports while passes
+  /// run. It is called on the thread driving the render.
+  pub fn set_progress_callback<F>(&mut self, callback: F)
+  where
+    F: FnMut(&Progress) + 'static,
+  {
+    self.progress_callback = Some(Box::new(callback));
+  }
+
+  /// Starts measuring progress against `remaining_spp` samples per pixel,
+  /// spread over the passes rendered until `end_job`. A pass rendered
+  /// outside a job is measured on its own. Camera ray footprints are sized
+  /// for `total_spp`, the samples the film will hold at the end, which
+  /// includes any taken before a resume.
+  pub fn begin_job(&mut self, total_spp: u32, remaining_spp: u32) {
+    let film = self.film();
+    let samples_total =
+      film.width as u64 * film.height as u64 * remaining_spp as u64;
+    std::mem::drop(film);
+
+    self.rays.store(0, Ordering::SeqCst);
+    self.job = Some(Job {
+      start: Instant::now(),
+      pass: 0,
+      samples_total,
+      samples_done: 0,
+      footprint: sample_footprint(total_spp),
+    });
+  }
+
+  pub fn end_job(&mut self) {
+    self.job = None;
+  }
+
+  fn report_progress(&mut self, tiles_done: usize, tiles_total: usize) {
+    let (job, callback) = match (&self.job, &mut self.progress_callback) {
+      (Some(job), Some(callback)) => (job, callback),
+      _ => return,
+    };
+
+    let elapsed = job.start.elapsed();
+    let fraction = if job.samples_total > 0 {
+      (job.samples_done as f64 / job.samples_total as f64).min(1_f64) as f32
+    } else {
+      1_f32
+    };
+
+    let eta = if fraction > 0_f32 {
+      Some(elapsed.mul_f32((1_f32 - fraction) / fraction))
+    } else {
+      None
+    };
+
+    let secs = elapsed.as_secs_f32();
+    let rays_per_second = if secs > 0_f32 {
+      self.rays.load(Ordering::SeqCst) as f32 / secs
+    } else {
+      0_f32
+    };
+
+    callback(&Progress {
+      pass: job.pass,
+      tiles_done,
+      tiles_total,
+      fraction,
+      elapsed,
+      eta,
+      rays_per_second,
+    });
+  }
+
+  /// Adds `spp` samples to every pixel of the film. `on_update` is invoked
+  /// periodically on the calling thread while the pass runs. Returns false
+  /// if the pass was cut short by cancellation.
+  pub fn render_pass<F>(&mut self, spp: u32, mut on_update: F) -> bool
+  where
+    F: FnMut(&Film),
+  {
+    let (nx, ny, filter) = {
+      let film = self.film();
+      (film.width, film.height, film.filter())
+    };
+
+    let owns_job = self.job.is_none();
+    if owns_job {
+      self.begin_job(spp, spp);
+    }
+
+    let footprint = match self.job.as_mut() {
+      Some(job) => {
+        job.pass += 1;
+        job.footprint
+      }
+      None => sample_footprint(spp),
+    };
+
+    let threads = self.schedule.threads.max(1);
+    let tiles =
+      generate_tiles(nx, ny, self.schedule.tile_size, self.schedule.order);
+    let tiles_total = tiles.len();
+    let work_queue = Arc::new(WorkQueue::new(tiles, threads));
+    let (tx, rx) = mpsc::channel();
+    let mut workers = Vec::new();
+
+    for worker in 0..threads {
+      let work_queue = work_queue.clone();
+      let tx = tx.clone();
+      let world = self.world.clone();
+      let film = self.film.clone();
+      let cancel = self.cancel.clone();
+      let cam = self.camera;
+      let filter = filter.clone();
+      let sampler = self.sampler;
+      let ray_counter = self.rays.clone();
+
+      let thread = thread::spawn(move || {
+        while !cancel.load(Ordering::SeqCst) {
+          let tile = match work_queue.next(worker) {
+            Some(tile) => tile,
+            None => break,
+          };
+
+          let mut rng = sampler.tile_rng(tile.index);
+          let mut rays = 0_u64;
+          let mut film_tile = FilmTile::new(tile.bounds, filter.clone(), nx, ny);
+          let mut finished = true;
+
+          for y in tile.bounds.ymin..tile.bounds.ymax {
+            if cancel.load(Ordering::SeqCst) {
+              finished = false;
+              break;
+            }
+
+            for x in tile.bounds.xmin..tile.bounds.xmax {
+              for _ in 0..spp {
+                let px = x as f32 + rng.gen::<f32>();
+                let py = y as f32 + rng.gen::<f32>();
+
+                let r = cam.ray_at(
+                  px / nx as f32,
+                  py / ny as f32,
+                  footprint / nx as f32,
+                  footprint / ny as f32,
+                );
+                let radiance = color(&r, &world, 0, 0, &mut rays);
+                film_tile.add_sample(px, py, radiance);
+              }
+            }
+          }
+
+          //
+          // A tile cut short holds part of a pass, which no sample count in
+          // a checkpoint could account for, so it is thrown away.
+          if !finished {
+            break;
+          }
+
+          film.lock().unwrap().merge_tile(&film_tile);
+          ray_counter.fetch_add(rays, Ordering::SeqCst);
+          tx.send(tile).unwrap();
+        }
+      });
+      workers.push(thread);
+    }
+
+    std::mem::drop(tx);
+
+    let mut tiles_done = 0;
+
+    loop {
+      match rx.recv_timeout(UPDATE_INTERVAL) {
+        Ok(tile) => {
+          tiles_done += 1;
+          if let Some(job) = self.job.as_mut() {
+            job.samples_done += tile.bounds.size() as u64 * spp as u64;
+          }
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) => {}
+        Err(mpsc::RecvTimeoutError::Disconnected) => break,
+      }
+
+      if let Some(deadline) = self.deadline {
+        if Instant::now() >= deadline {
+          self.cancel.store(true, Ordering::SeqCst);
+        }
+      }
+
+      self.report_progress(tiles_done, tiles_total);
+      on_update(&self.film());
+    }
+
+    for t in workers {
+      t.join().unwrap();
+    }
+
+    if owns_job {
+      self.end_job();
+    }
+
+    self.sampler.advance();
+    !self.is_cancelled()
+  }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct ProgressiveSettings {
+  /// Samples per pixel already in the film, e.g. from a checkpoint.
+  pub start_spp: u32,
+  /// Stop once every pixel has received this many samples.
+  pub target_spp: u32,
+  /// Stop once this much time has passed since the start of the render.
+  pub time_budget: Option<Duration>,
+  /// Write a preview every time this much time has passed.
+  pub preview_interval: Option<Duration>,
+  /// Write a preview after this many passes.
+  pub preview_passes: Option<u32>,
+  /// Write a checkpoint after the first pass that ends this long after the
+  /// previous one, and once more when the render stops.
+  pub checkpoint_interval: Option<Duration>,
+}
+
+/// Renders in passes of increasing sample counts until the target sample
+/// count or the time budget is reached, or the render is cancelled.
+/// `write_preview` receives the film whenever a preview is due and
+/// `write_checkpoint` the renderer and the completed samples per pixel
+/// whenever a checkpoint is due. Returns the number of samples per pixel of
+/// all the completed passes.
+pub fn render_progressive<P, C>(
+  renderer: &mut Renderer,
+  settings: &ProgressiveSettings,
+  mut write_preview: P,
+  mut write_checkpoint: C,
+) -> u32
+where
+  P: FnMut(&Film),
+  C: FnMut(&Renderer, u32),
+{
+  let start = Instant::now();
+  renderer.set_deadline(settings.time_budget.map(|budget| start + budget));
+
+  let mut spp_done = settings.start_spp;
+  let mut passes = 0;
+  renderer.begin_job(
+    settings.target_spp,
+    settings.target_spp.saturating_sub(spp_done),
+  );
+  let mut last_preview = Instant::now();
+  let mut last_checkpoint = Instant::now();
+
+  while spp_done < settings.target_spp {
+    let pass_spp = spp_done
+      .clamp(1, MAX_PASS_SPP)
+      .min(settings.target_spp - spp_done);
+
+    let completed = renderer.render_pass(pass_spp, |film| {
+      if let Some(interval) = settings.preview_interval {
+        if last_preview.elapsed() >= interval {
+          write_preview(film);
+          last_preview = Instant::now();
+        }
+      }
+    });
+
+    if !completed {
+      break;
+    }
+
+    spp_done += pass_spp;
+    passes += 1;
+
+    if let Some(preview_passes) = settings.preview_passes {
+      if passes % preview_passes == 0 {
+        write_preview(&renderer.film());
+        last_preview = Instant::now();
+      }
+    }
+
+    if let Some(interval) = settings.checkpoint_interval {
+      if last_checkpoint.elapsed() >= interval {
+        write_checkpoint(renderer, spp_done);
+        last_checkpoint = Instant::now();
+      }
+    }
+  }
+
+  renderer.end_job();
+
+  if settings.checkpoint_interval.is_some() {
+    write_checkpoint(renderer, spp_done);
+  }
+
+  spp_done
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::aabb::Aabb;
+  use crate::filter::FilterKind;
+  use crate::hitable::HitRecord;
+
+  /// Cancels the render the first time a ray reaches it.
+  struct Tripwire(Mutex<Option<Arc<AtomicBool>>>);
+
+  impl Hitable for Tripwire {
+    fn hit(&self, _r: &Ray, _t_min: f32, _t_max: f32) -> Option<HitRecord> {
+      if let Some(cancel) = self.0.lock().unwrap().as_ref() {
+        cancel.store(true, Ordering::SeqCst);
+      }
+      None
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+      None
+    }
+  }
+
+  fn test_camera() -> Camera {
+    Camera::new(
+      Vec3::new(0_f32, 0_f32, 1_f32),
+      Vec3::same(0_f32),
+      Vec3::new(0_f32, 1_f32, 0_f32),
+      90_f32,
+      1_f32,
+      0_f32,
+      1_f32,
+      0_f32,
+      1_f32,
+    )
+  }
+
+  #[test]
+  fn test_resumed_job_keeps_the_footprint() {
+    let film = Film::new(4, 4, FilterKind::Box.create(0.5_f32));
+    let world: Arc<dyn Hitable> = Arc::new(Tripwire(Mutex::new(None)));
+    let mut renderer =
+      Renderer::new(world, test_camera(), film, Sampler::new(0));
+
+    //
+    // Resumed at 900 of 1000 samples: the footprint is that of the whole
+    // render, the progress that of the rest.
+    renderer.begin_job(1000, 100);
+    let job = renderer.job.as_ref().unwrap();
+    assert_eq!(job.footprint, sample_footprint(1000));
+    assert_eq!(job.samples_total, 4 * 4 * 100);
+  }
+
+  #[test]
+  fn test_cancelled_tile_is_dropped() {
+    let tripwire = Arc::new(Tripwire(Mutex::new(None)));
+    let film = Film::new(4, 4, FilterKind::Box.create(0.5_f32));
+    let mut renderer =
+      Renderer::new(tripwire.clone(), test_camera(), film, Sampler::new(0));
+    renderer.set_schedule(TileSchedule {
+      threads: 1,
+      tile_size: 4,
+      order: TileOrder::Scanline,
+    });
+    *tripwire.0.lock().unwrap() = Some(renderer.cancel_flag());
+
+    //
+    // The first row of the only tile is finished before the worker sees
+    // the flag.
+    assert!(!renderer.render_pass(1, |_| {}));
+    assert!(renderer.film().pixels().iter().all(|p| p.sample_count == 0));
+  }
+}
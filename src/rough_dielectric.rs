@@ -0,0 +1,121 @@
+use super::constant_texture::ConstantTexture;
+use super::dielectric::{absorption_from_transmittance, interior_attenuation};
+use super::fresnel::fresnel_dielectric;
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::microfacet::GgxDistribution;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::texture::Texture;
+use super::vec3::{dot_product, reflect, refract, unit_vector, Vec3};
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// Glass with a GGX microfacet surface that blurs both reflection and
+/// refraction (Walter et al., "Microfacet Models for Refraction through
+/// Rough Surfaces", 2007), for frosted glass and similar.
+pub struct RoughDielectric {
+  pub ref_idx: f32,
+  /// Perceptually linear roughness, read as a single channel.
+  pub roughness: Arc<dyn Texture>,
+  /// Beer-Lambert absorption coefficient, see `Dielectric::absorption`.
+  pub absorption: Vec3,
+}
+
+impl RoughDielectric {
+  pub fn new(ri: f32, roughness: Arc<dyn Texture>) -> RoughDielectric {
+    RoughDielectric {
+      ref_idx: ri,
+      roughness,
+      absorption: Vec3::same(0_f32),
+    }
+  }
+
+  /// Tints the medium so that `color` of the light is left after
+  /// `distance` units.
+  pub fn tinted(mut self, color: Vec3, distance: f32) -> RoughDielectric {
+    self.absorption = absorption_from_transmittance(color, distance);
+    self
+  }
+
+  pub fn with_roughness(ri: f32, roughness: f32) -> RoughDielectric {
+    RoughDielectric::new(
+      ri,
+      Arc::new(ConstantTexture::new(Vec3::same(roughness))),
+    )
+  }
+}
+
+/// Samples the GGX interface between two dielectrics for a ray arriving
+/// along `direction` at a surface with the given outward `normal`. Returns
+/// the outgoing direction, its G2 / G1 weight and whether it refracted.
+pub fn sample_rough_interface(
+  direction: Vec3,
+  normal: Vec3,
+  ref_idx: f32,
+  distribution: &GgxDistribution,
+) -> Option<(Vec3, f32, bool)> {
+  let unit_direction = unit_vector(direction);
+  let (n, eta) = if dot_product(unit_direction, normal) > 0_f32 {
+    (-normal, 1_f32 / ref_idx)
+  } else {
+    (normal, ref_idx)
+  };
+
+  let frame = Onb::from_w(n);
+  let wo = frame.to_local(-unit_direction);
+  if wo.z <= 0_f32 {
+    return None;
+  }
+
+  let mut rng = thread_rng();
+  let m = distribution.sample_visible_normal(wo, rng.gen(), rng.gen());
+  let cos_om = dot_product(wo, m);
+
+  //
+  // Choosing between reflection and refraction with probability F
+  // cancels the Fresnel term, leaving G2 / G1 for either branch.
+  let fresnel = fresnel_dielectric(cos_om, eta);
+  let refracted = if rng.gen::<f32>() < fresnel {
+    None
+  } else {
+    refract(-wo, m, 1_f32 / eta)
+  };
+
+  let (wi, is_refraction) = match refracted {
+    Some(wi) if wi.z < 0_f32 => (wi, true),
+    Some(_) => return None,
+    None => {
+      let wi = reflect(-wo, m);
+      if wi.z <= 0_f32 {
+        return None;
+      }
+      (wi, false)
+    }
+  };
+
+  Some((
+    frame.to_world(unit_vector(wi)),
+    distribution.g2(wo, wi) / distribution.g1(wo),
+    is_refraction,
+  ))
+}
+
+impl Material for RoughDielectric {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    let roughness = self.roughness.scalar_at(r, h).clamp(0_f32, 1_f32);
+    let distribution = GgxDistribution::isotropic(
+      GgxDistribution::roughness_to_alpha(roughness),
+    );
+
+    let (direction, weight, _) = sample_rough_interface(
+      r.direction,
+      h.normal,
+      self.ref_idx,
+      &distribution,
+    )?;
+    let attenuation = interior_attenuation(r, h, self.absorption) * weight;
+
+    Some((attenuation, Ray::new(h.p, direction, r.time)))
+  }
+}
@@ -0,0 +1,35 @@
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+fn splitmix64(x: u64) -> u64 {
+  let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+  z ^ (z >> 31)
+}
+
+/// Hands out the random streams used to jitter pixel samples. Every
+/// (pass, tile) pair gets its own stream, so the whole state is the seed
+/// and the number of passes taken so far. Restoring that state lets a
+/// resumed render continue with fresh sample positions instead of
+/// replaying the ones already in the film.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sampler {
+  pub seed: u64,
+  pub pass: u64,
+}
+
+impl Sampler {
+  pub fn new(seed: u64) -> Sampler {
+    Sampler { seed, pass: 0 }
+  }
+
+  pub fn tile_rng(&self, tile: usize) -> StdRng {
+    let stream = splitmix64(self.seed ^ splitmix64(self.pass));
+    StdRng::seed_from_u64(splitmix64(stream ^ tile as u64))
+  }
+
+  pub fn advance(&mut self) {
+    self.pass += 1;
+  }
+}
@@ -2,23 +2,27 @@ use super::aabb::Aabb;
 use super::hitable::{HitRecord, Hitable};
 use super::material::Material;
 use super::ray::Ray;
-use super::vec3::{dot_product, Vec3};
+use super::vec3::{dot_product, unit_vector, Vec3};
 use std::sync::Arc;
 
 pub struct Sphere {
   pub center: Vec3,
   pub radius: f32,
-  pub mtl: Arc<Material>,
+  pub mtl: Arc<dyn Material>,
 }
 
 impl Sphere {
-  pub fn new(center: Vec3, radius: f32, mtl: Arc<Material>) -> Sphere {
+  pub fn new(center: Vec3, radius: f32, mtl: Arc<dyn Material>) -> Sphere {
     Sphere {
       center,
       radius,
       mtl,
     }
   }
+
+  pub fn area(&self) -> f32 {
+    4_f32 * std::f32::consts::PI * self.radius * self.radius
+  }
 }
 
 pub fn get_sphere_uv(p: Vec3) -> (f32, f32) {
@@ -30,6 +34,30 @@ pub fn get_sphere_uv(p: Vec3) -> (f32, f32) {
   )
 }
 
+/// Direction of increasing u from `get_sphere_uv` at the unit normal `n`,
+/// which circles the poles.
+pub fn get_sphere_tangent(n: Vec3) -> Vec3 {
+  let t = Vec3::new(n.z, 0_f32, -n.x);
+  if t.squared_length() > 1e-12_f32 {
+    unit_vector(t)
+  } else {
+    Vec3::new(1_f32, 0_f32, 0_f32)
+  }
+}
+
+/// Derivatives of position with respect to the (u, v) of `get_sphere_uv`
+/// at the unit normal `n`, on a sphere of the given radius.
+pub fn get_sphere_derivatives(n: Vec3, radius: f32) -> (Vec3, Vec3) {
+  let pi = std::f32::consts::PI;
+  let cos_theta = (n.x * n.x + n.z * n.z).sqrt().max(1e-6_f32);
+
+  let dpdu = 2_f32 * pi * radius * Vec3::new(n.z, 0_f32, -n.x);
+  let dpdv = pi
+    * radius
+    * Vec3::new(-n.y * n.x / cos_theta, cos_theta, -n.y * n.z / cos_theta);
+  (dpdu, dpdv)
+}
+
 impl Hitable for Sphere {
   fn hit(&self, r: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
     let oc = r.origin - self.center;
@@ -45,8 +73,18 @@ impl Hitable for Sphere {
         let p = r.point_at_param(temp);
         let n = (p - self.center) / self.radius;
         let (u, v) = get_sphere_uv(n);
+        let (dpdu, dpdv) = get_sphere_derivatives(n, self.radius);
 
-        return Some(HitRecord::new(temp, p, n, self.mtl.clone(), u, v));
+        return Some(
+          HitRecord::new(temp, p, n, self.mtl.clone(), u, v)
+            .with_tangent(get_sphere_tangent(n))
+            .with_derivatives(
+              dpdu,
+              dpdv,
+              dpdu / self.radius,
+              dpdv / self.radius,
+            ),
+        );
       }
 
       let temp = (-b + (b * b - a * c).sqrt()) / a;
@@ -54,8 +92,18 @@ impl Hitable for Sphere {
         let p = r.point_at_param(temp);
         let n = (p - self.center) / self.radius;
         let (u, v) = get_sphere_uv(n);
+        let (dpdu, dpdv) = get_sphere_derivatives(n, self.radius);
 
-        return Some(HitRecord::new(temp, p, n, self.mtl.clone(), u, v));
+        return Some(
+          HitRecord::new(temp, p, n, self.mtl.clone(), u, v)
+            .with_tangent(get_sphere_tangent(n))
+            .with_derivatives(
+              dpdu,
+              dpdv,
+              dpdu / self.radius,
+              dpdv / self.radius,
+            ),
+        );
       }
     }
 
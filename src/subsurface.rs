@@ -0,0 +1,211 @@
+use super::fresnel::fresnel_dielectric;
+use super::hitable::HitRecord;
+use super::material::Material;
+use super::onb::Onb;
+use super::ray::Ray;
+use super::vec3::{dot_product, reflect, refract, unit_vector, Vec3};
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+/// A dielectric boundary around a scattering medium, for wax, skin,
+/// marble or milk. Light refracts in through the surface, random walks
+/// through the medium and refracts out again somewhere else.
+///
+/// The walk runs one segment per call: each ray inside the object reaches
+/// the boundary, and a free-flight distance sampled against it decides
+/// whether the light scattered before getting there. These segments are
+/// medium steps, so long walks aren't cut off by the bounce limit. The
+/// object has to be closed, with nothing inside it.
+pub struct Subsurface {
+  pub ior: f32,
+  /// Scattering coefficient per unit of distance, per channel.
+  pub scattering: Vec3,
+  /// Absorption coefficient per unit of distance, per channel.
+  pub absorption: Vec3,
+  /// Henyey-Greenstein anisotropy in (-1, 1); positive scatters forward.
+  pub g: f32,
+}
+
+/// Samples the cosine between the incoming and scattered directions from
+/// the Henyey-Greenstein phase function.
+pub fn sample_henyey_greenstein(g: f32, u: f32) -> f32 {
+  if g.abs() < 1e-3_f32 {
+    return 1_f32 - 2_f32 * u;
+  }
+
+  let t = (1_f32 - g * g) / (1_f32 - g + 2_f32 * g * u);
+  ((1_f32 + g * g - t * t) / (2_f32 * g)).clamp(-1_f32, 1_f32)
+}
+
+impl Subsurface {
+  pub fn new(
+    ior: f32,
+    scattering: Vec3,
+    absorption: Vec3,
+    g: f32,
+  ) -> Subsurface {
+    Subsurface {
+      ior,
+      scattering,
+      absorption,
+      g: g.clamp(-0.99_f32, 0.99_f32),
+    }
+  }
+
+  fn extinction(&self) -> Vec3 {
+    self.scattering + self.absorption
+  }
+
+  /// Refracts or reflects at the boundary with Fresnel probability.
+  fn cross_boundary(&self, direction: Vec3, normal: Vec3) -> Vec3 {
+    let unit_direction = unit_vector(direction);
+    let (n, eta) = if dot_product(unit_direction, normal) > 0_f32 {
+      (-normal, 1_f32 / self.ior)
+    } else {
+      (normal, self.ior)
+    };
+
+    let cosine = -dot_product(unit_direction, n);
+    match refract(unit_direction, n, 1_f32 / eta) {
+      Some(refracted)
+        if thread_rng().gen::<f32>() >= fresnel_dielectric(cosine, eta) =>
+      {
+        refracted
+      }
+      _ => reflect(unit_direction, n),
+    }
+  }
+}
+
+impl Material for Subsurface {
+  fn scatter(&self, r: &Ray, h: &HitRecord) -> Option<(Vec3, Ray)> {
+    if !self.is_medium_step(r, h) {
+      let direction = self.cross_boundary(r.direction, h.normal);
+      return Some((Vec3::same(1_f32), Ray::new(h.p, direction, r.time)));
+    }
+
+    //
+    // Inside: sample a free flight with the extinction of a randomly
+    // picked channel. Weighting by the mean density over all channels
+    // keeps the colour right whichever channel was picked.
+    let mut rng = thread_rng();
+    let length = r.direction.length();
+    let boundary = h.t * length;
+    let sigma_t = self.extinction();
+    let channel = rng.gen_range(0_u32, 3) as usize;
+    let distance = if sigma_t[channel] > 0_f32 {
+      -(1_f32 - rng.gen::<f32>()).ln() / sigma_t[channel]
+    } else {
+      f32::MAX
+    };
+
+    let transmittance = |d: f32| {
+      Vec3::new(
+        (-sigma_t.x * d).exp(),
+        (-sigma_t.y * d).exp(),
+        (-sigma_t.z * d).exp(),
+      )
+    };
+    let mean = |v: Vec3| (v.x + v.y + v.z) / 3_f32;
+
+    if distance >= boundary {
+      let tr = transmittance(boundary);
+      let pdf = mean(tr);
+      if pdf <= 0_f32 {
+        return None;
+      }
+
+      let direction = self.cross_boundary(r.direction, h.normal);
+      return Some((tr / pdf, Ray::new(h.p, direction, r.time)));
+    }
+
+    let tr = transmittance(distance);
+    let pdf = mean(sigma_t * tr);
+    if pdf <= 0_f32 {
+      return None;
+    }
+
+    let forward = unit_vector(r.direction);
+    let cos_theta = sample_henyey_greenstein(self.g, rng.gen());
+    let sin_theta = (1_f32 - cos_theta * cos_theta).max(0_f32).sqrt();
+    let phi = 2_f32 * PI * rng.gen::<f32>();
+    let direction = Onb::from_w(forward).to_world(Vec3::new(
+      sin_theta * phi.cos(),
+      sin_theta * phi.sin(),
+      cos_theta,
+    ));
+
+    Some((
+      self.scattering * tr / pdf,
+      Ray::new(r.point_at_param(distance / length), direction, r.time),
+    ))
+  }
+
+  /// Every ray travelling inside the object is part of the walk.
+  fn is_medium_step(&self, r: &Ray, h: &HitRecord) -> bool {
+    dot_product(r.direction, h.normal) >= 0_f32
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+  use crate::diffuse_light::DiffuseLight;
+  use crate::hitable::Hitable;
+  use crate::hitable_list::HitableList;
+  use crate::renderer::color;
+  use crate::sphere::Sphere;
+  use std::sync::Arc;
+
+  #[test]
+  fn test_henyey_greenstein_mean_cosine() {
+    //
+    // The mean cosine of the phase function is g.
+    for &g in [-0.5_f32, 0_f32, 0.3_f32, 0.8_f32].iter() {
+      let n = 10000;
+      let mean: f32 = (0..n)
+        .map(|i| sample_henyey_greenstein(g, (i as f32 + 0.5_f32) / n as f32))
+        .sum::<f32>()
+        / n as f32;
+      assert!((mean - g).abs() < 1e-2_f32, "{} {}", g, mean);
+    }
+  }
+
+  #[test]
+  fn test_dense_medium_walk_is_not_cut_off() {
+    //
+    // A white medium inside a white sky loses no light, so every path
+    // should come back with the sky's radiance, however long its walk.
+    let mut world = HitableList::new();
+    world.add_object(Arc::new(Sphere::new(
+      Vec3::same(0_f32),
+      1_f32,
+      Arc::new(Subsurface::new(
+        1.3_f32,
+        Vec3::same(10_f32),
+        Vec3::same(0_f32),
+        0_f32,
+      )),
+    )));
+    world.add_object(Arc::new(Sphere::new(
+      Vec3::same(0_f32),
+      10_f32,
+      Arc::new(DiffuseLight::new(Arc::new(ConstantTexture::same(1_f32)))),
+    )));
+    let world: Arc<dyn Hitable> = Arc::new(world);
+
+    let n = 400;
+    let r = Ray::new(
+      Vec3::new(0_f32, 0_f32, 5_f32),
+      Vec3::new(0_f32, 0_f32, -1_f32),
+      0_f32,
+    );
+    let mut rays = 0_u64;
+    let mean = (0..n)
+      .map(|_| color(&r, &world, 0, 0, &mut rays).x)
+      .sum::<f32>()
+      / n as f32;
+    assert!(mean > 0.99_f32, "{}", mean);
+  }
+}
@@ -1,5 +1,69 @@
-use super::vec3::Vec3;
+use super::hitable::HitRecord;
+use super::ray::Ray;
+use super::ray_differential::{footprint, Footprint};
+use super::vec3::{unit_vector, Vec3};
+
+/// Everything a texture may be looked up by at a point on a surface.
+#[derive(Copy, Clone, Debug)]
+pub struct TexturePoint {
+  pub u : f32,
+  pub v : f32,
+  pub p : Vec3,
+  /// Shading normal, for textures projected in world space.
+  pub normal : Vec3,
+  /// The area the pixel covers, when the ray carries differentials.
+  pub footprint : Option<Footprint>,
+}
+
+impl TexturePoint {
+  /// A point known only by its coordinates, with no footprint and a
+  /// normal along the diagonal, which favours no axis.
+  pub fn new(u : f32, v : f32, p : Vec3) -> TexturePoint {
+    TexturePoint {
+      u,
+      v,
+      p,
+      normal : unit_vector(Vec3::same(1_f32)),
+      footprint : None,
+    }
+  }
+
+  pub fn at(r : &Ray, h : &HitRecord) -> TexturePoint {
+    TexturePoint {
+      u : h.u,
+      v : h.v,
+      p : h.p,
+      normal : h.normal,
+      footprint : footprint(r, h),
+    }
+  }
+}
 
 pub trait Texture : Send + Sync {
-  fn value(&self, u : f32, v : f32, p : Vec3) -> Vec3;
-}
\ No newline at end of file
+  /// The texture at a point on a surface, averaged over the footprint
+  /// when there is one. Textures built on other textures pass the whole
+  /// point on, so the ones that need the normal or the footprint get them.
+  fn lookup(&self, at : &TexturePoint) -> Vec3;
+
+  /// The texture at a bare point, for lookups made without a hit.
+  fn value(&self, u : f32, v : f32, p : Vec3) -> Vec3 {
+    self.lookup(&TexturePoint::new(u, v, p))
+  }
+
+  /// The texture where `r` hit.
+  fn value_at(&self, r : &Ray, h : &HitRecord) -> Vec3 {
+    self.lookup(&TexturePoint::at(r, h))
+  }
+
+  /// The texture read as a single channel, for parameters such as
+  /// roughness or weights.
+  fn scalar(&self, at : &TexturePoint) -> f32 {
+    let c = self.lookup(at);
+    (c.x + c.y + c.z) / 3_f32
+  }
+
+  /// `scalar` where `r` hit.
+  fn scalar_at(&self, r : &Ray, h : &HitRecord) -> f32 {
+    self.scalar(&TexturePoint::at(r, h))
+  }
+}
@@ -0,0 +1,212 @@
+use super::color_ramp::ColorRamp;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+/// A texture multiplied by a constant factor.
+pub struct ScaleTexture {
+  inner: Arc<dyn Texture>,
+  factor: f32,
+}
+
+impl ScaleTexture {
+  pub fn new(inner: Arc<dyn Texture>, factor: f32) -> ScaleTexture {
+    ScaleTexture { inner, factor }
+  }
+}
+
+impl Texture for ScaleTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.factor * self.inner.lookup(at)
+  }
+}
+
+/// Product of two textures per channel, for tinting or masking.
+pub struct MultiplyTexture {
+  a: Arc<dyn Texture>,
+  b: Arc<dyn Texture>,
+}
+
+impl MultiplyTexture {
+  pub fn new(a: Arc<dyn Texture>, b: Arc<dyn Texture>) -> MultiplyTexture {
+    MultiplyTexture { a, b }
+  }
+}
+
+impl Texture for MultiplyTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.a.lookup(at) * self.b.lookup(at)
+  }
+}
+
+/// Sum of two textures per channel.
+pub struct AddTexture {
+  a: Arc<dyn Texture>,
+  b: Arc<dyn Texture>,
+}
+
+impl AddTexture {
+  pub fn new(a: Arc<dyn Texture>, b: Arc<dyn Texture>) -> AddTexture {
+    AddTexture { a, b }
+  }
+}
+
+impl Texture for AddTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.a.lookup(at) + self.b.lookup(at)
+  }
+}
+
+/// Blends from `a` to `b` as `factor`, read as a single channel, goes
+/// from 0 to 1.
+pub struct MixTexture {
+  a: Arc<dyn Texture>,
+  b: Arc<dyn Texture>,
+  factor: Arc<dyn Texture>,
+}
+
+impl MixTexture {
+  pub fn new(
+    a: Arc<dyn Texture>,
+    b: Arc<dyn Texture>,
+    factor: Arc<dyn Texture>,
+  ) -> MixTexture {
+    MixTexture { a, b, factor }
+  }
+}
+
+impl MixTexture {
+  /// Blends `lookup` of the two inputs by the factor `t`, skipping
+  /// whichever has no weight.
+  fn blend<F: Fn(&dyn Texture) -> Vec3>(&self, t: f32, lookup: F) -> Vec3 {
+    let t = t.clamp(0_f32, 1_f32);
+    if t <= 0_f32 {
+      return lookup(&*self.a);
+    }
+    if t >= 1_f32 {
+      return lookup(&*self.b);
+    }
+
+    (1_f32 - t) * lookup(&*self.a) + t * lookup(&*self.b)
+  }
+}
+
+impl Texture for MixTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.blend(self.factor.scalar(at), |t| t.lookup(at))
+  }
+}
+
+/// Maps a texture, read as a single channel, through a colour ramp.
+pub struct RampTexture {
+  inner: Arc<dyn Texture>,
+  ramp: ColorRamp,
+}
+
+impl RampTexture {
+  pub fn new(inner: Arc<dyn Texture>, ramp: ColorRamp) -> RampTexture {
+    RampTexture { inner, ramp }
+  }
+}
+
+impl Texture for RampTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.ramp.evaluate(self.inner.scalar(at))
+  }
+}
+
+/// One minus a texture, per channel.
+pub struct InvertTexture {
+  inner: Arc<dyn Texture>,
+}
+
+impl InvertTexture {
+  pub fn new(inner: Arc<dyn Texture>) -> InvertTexture {
+    InvertTexture { inner }
+  }
+}
+
+impl Texture for InvertTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    Vec3::same(1_f32) - self.inner.lookup(at)
+  }
+}
+
+/// A texture with each channel clamped to `[min, max]`, with `min <= max`.
+pub struct ClampTexture {
+  inner: Arc<dyn Texture>,
+  min: f32,
+  max: f32,
+}
+
+impl ClampTexture {
+  pub fn new(inner: Arc<dyn Texture>, min: f32, max: f32) -> ClampTexture {
+    assert!(min <= max, "clamp bounds must be ordered and not NaN");
+    ClampTexture { inner, min, max }
+  }
+}
+
+impl ClampTexture {
+  fn clamp(&self, c: Vec3) -> Vec3 {
+    Vec3::new(
+      c.x.clamp(self.min, self.max),
+      c.y.clamp(self.min, self.max),
+      c.z.clamp(self.min, self.max),
+    )
+  }
+}
+
+impl Texture for ClampTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.clamp(self.inner.lookup(at))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+
+  #[test]
+  fn test_composed_graph() {
+    let grey: Arc<dyn Texture> = Arc::new(ConstantTexture::same(0.25_f32));
+    let red: Arc<dyn Texture> =
+      Arc::new(ConstantTexture::new(Vec3::new(1_f32, 0_f32, 0_f32)));
+
+    //
+    // clamp(invert(2 * grey) + red) = clamp((1.5, 0.5, 0.5)).
+    let graph = ClampTexture::new(
+      Arc::new(AddTexture::new(
+        Arc::new(InvertTexture::new(Arc::new(ScaleTexture::new(
+          grey.clone(),
+          2_f32,
+        )))),
+        red.clone(),
+      )),
+      0_f32,
+      1_f32,
+    );
+    let p = Vec3::same(0_f32);
+    assert_eq!(
+      graph.value(0_f32, 0_f32, p),
+      Vec3::new(1_f32, 0.5_f32, 0.5_f32)
+    );
+
+    let mix = MixTexture::new(red, grey.clone(), grey);
+    let expected = Vec3::new(0.8125_f32, 0.0625_f32, 0.0625_f32);
+    assert!((mix.value(0_f32, 0_f32, p) - expected).length() < 1e-6_f32);
+  }
+
+  #[test]
+  #[should_panic(expected = "clamp bounds")]
+  fn test_clamp_rejects_reversed_bounds() {
+    ClampTexture::new(Arc::new(ConstantTexture::same(0.5_f32)), 1_f32, 0_f32);
+  }
+
+  #[test]
+  #[should_panic(expected = "clamp bounds")]
+  fn test_clamp_rejects_nan_bounds() {
+    let grey = Arc::new(ConstantTexture::same(0.5_f32));
+    ClampTexture::new(grey, 0_f32, f32::NAN);
+  }
+}
@@ -0,0 +1,183 @@
+use super::ray_differential::Footprint;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::{cross_product, dot_product, unit_vector, Vec3};
+use std::sync::Arc;
+
+/// Moves the texture coordinates before looking up another texture. The
+/// coordinates passed on are `rotate(scale * (u, v)) + offset`, so a
+/// scale of 2 repeats the texture twice as often.
+pub struct UvTransform {
+  inner: Arc<dyn Texture>,
+  scale: (f32, f32),
+  /// Counter clockwise, in radians.
+  rotation: f32,
+  offset: (f32, f32),
+}
+
+impl UvTransform {
+  pub fn new(inner: Arc<dyn Texture>) -> UvTransform {
+    UvTransform {
+      inner,
+      scale: (1_f32, 1_f32),
+      rotation: 0_f32,
+      offset: (0_f32, 0_f32),
+    }
+  }
+
+  pub fn with_scale(mut self, u: f32, v: f32) -> UvTransform {
+    self.scale = (u, v);
+    self
+  }
+
+  pub fn with_rotation(mut self, degrees: f32) -> UvTransform {
+    self.rotation = degrees.to_radians();
+    self
+  }
+
+  pub fn with_offset(mut self, u: f32, v: f32) -> UvTransform {
+    self.offset = (u, v);
+    self
+  }
+}
+
+impl UvTransform {
+  /// The transform without the offset, which also maps changes in (u, v).
+  fn linear(&self, u: f32, v: f32) -> (f32, f32) {
+    let (su, sv) = (self.scale.0 * u, self.scale.1 * v);
+    let (sin, cos) = self.rotation.sin_cos();
+    (cos * su - sin * sv, sin * su + cos * sv)
+  }
+}
+
+impl Texture for UvTransform {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    let (u, v) = self.linear(at.u, at.v);
+    let footprint = at.footprint.map(|f| {
+      let (dudx, dvdx) = self.linear(f.dudx, f.dvdx);
+      let (dudy, dvdy) = self.linear(f.dudy, f.dvdy);
+      Footprint {
+        dudx,
+        dvdx,
+        dudy,
+        dvdy,
+        ..f
+      }
+    });
+    self.inner.lookup(&TexturePoint {
+      u: u + self.offset.0,
+      v: v + self.offset.1,
+      footprint,
+      ..*at
+    })
+  }
+}
+
+/// Moves the point a solid texture is looked up at. The point passed on
+/// is `rotate(scale * p) + offset`.
+pub struct PositionTransform {
+  inner: Arc<dyn Texture>,
+  scale: Vec3,
+  axis: Vec3,
+  /// About `axis`, in radians.
+  rotation: f32,
+  offset: Vec3,
+}
+
+impl PositionTransform {
+  pub fn new(inner: Arc<dyn Texture>) -> PositionTransform {
+    PositionTransform {
+      inner,
+      scale: Vec3::same(1_f32),
+      axis: Vec3::new(0_f32, 1_f32, 0_f32),
+      rotation: 0_f32,
+      offset: Vec3::same(0_f32),
+    }
+  }
+
+  pub fn with_scale(mut self, scale: Vec3) -> PositionTransform {
+    self.scale = scale;
+    self
+  }
+
+  pub fn with_rotation(
+    mut self,
+    axis: Vec3,
+    degrees: f32,
+  ) -> PositionTransform {
+    self.axis = unit_vector(axis);
+    self.rotation = degrees.to_radians();
+    self
+  }
+
+  pub fn with_offset(mut self, offset: Vec3) -> PositionTransform {
+    self.offset = offset;
+    self
+  }
+}
+
+impl PositionTransform {
+  fn rotate(&self, q: Vec3) -> Vec3 {
+    //
+    // Rodrigues' rotation formula.
+    let (sin, cos) = self.rotation.sin_cos();
+    let k = self.axis;
+    cos * q + sin * cross_product(k, q) + (1_f32 - cos) * dot_product(k, q) * k
+  }
+
+  fn transform(&self, p: Vec3) -> Vec3 {
+    self.rotate(self.scale * p) + self.offset
+  }
+}
+
+impl Texture for PositionTransform {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    //
+    // Normals take the inverse scale, so they stay perpendicular to the
+    // surface when it is stretched.
+    let footprint = at.footprint.map(|f| Footprint {
+      dpdx: self.rotate(self.scale * f.dpdx),
+      dpdy: self.rotate(self.scale * f.dpdy),
+      ..f
+    });
+    self.inner.lookup(&TexturePoint {
+      p: self.transform(at.p),
+      normal: unit_vector(self.rotate(at.normal / self.scale)),
+      footprint,
+      ..*at
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Returns its lookup coordinates as a colour.
+  struct Coordinates;
+
+  impl Texture for Coordinates {
+    fn lookup(&self, at: &TexturePoint) -> Vec3 {
+      Vec3::new(at.u, at.v, 0_f32) + at.p
+    }
+  }
+
+  #[test]
+  fn test_uv_transform_order() {
+    let t = UvTransform::new(Arc::new(Coordinates))
+      .with_scale(2_f32, 1_f32)
+      .with_rotation(90_f32)
+      .with_offset(0.5_f32, 0_f32);
+
+    let c = t.value(1_f32, 0_f32, Vec3::same(0_f32));
+    assert!((c - Vec3::new(0.5_f32, 2_f32, 0_f32)).length() < 1e-5_f32);
+  }
+
+  #[test]
+  fn test_position_rotation() {
+    let t = PositionTransform::new(Arc::new(Coordinates))
+      .with_rotation(Vec3::new(0_f32, 0_f32, 1_f32), 90_f32);
+
+    let c = t.value(0_f32, 0_f32, Vec3::new(1_f32, 0_f32, 0_f32));
+    assert!((c - Vec3::new(0_f32, 1_f32, 0_f32)).length() < 1e-5_f32);
+  }
+}
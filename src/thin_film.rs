@@ -0,0 +1,291 @@
+use super::hitable::HitRecord;
+use super::ray::Ray;
+use super::texture::Texture;
+use super::vec3::Vec3;
+use std::ops::{Add, Div, Mul, Sub};
+use std::sync::Arc;
+
+/// Wavelengths in nanometres at which the red, green and blue channels
+/// are evaluated.
+const WAVELENGTHS: [f32; 3] = [630_f32, 532_f32, 465_f32];
+
+/// A thin transparent layer on top of a surface, such as a soap film or an
+/// anti-reflective coating. Light reflected off its two sides interferes,
+/// so the reflectance changes with the wavelength, the thickness of the
+/// film and the viewing angle.
+pub struct ThinFilm {
+  pub ior: f32,
+  /// Read as a single channel and mapped from [0, 1] onto
+  /// `min_thickness..max_thickness`.
+  pub thickness: Arc<dyn Texture>,
+  /// In nanometres.
+  pub min_thickness: f32,
+  pub max_thickness: f32,
+}
+
+impl ThinFilm {
+  pub fn new(
+    ior: f32,
+    thickness: Arc<dyn Texture>,
+    min_thickness: f32,
+    max_thickness: f32,
+  ) -> ThinFilm {
+    ThinFilm {
+      ior,
+      thickness,
+      min_thickness,
+      max_thickness,
+    }
+  }
+
+  /// Thickness in nanometres where `r` hit the surface.
+  pub fn thickness_at(&self, r: &Ray, h: &HitRecord) -> f32 {
+    let t = self.thickness.scalar_at(r, h).clamp(0_f32, 1_f32);
+    self.min_thickness + (self.max_thickness - self.min_thickness) * t
+  }
+
+  /// Reflectance per RGB channel for light arriving at `cos_i` to the
+  /// normal from a medium of index `outside`, with the film lying on a
+  /// substrate of complex index `substrate_eta + i substrate_k`.
+  pub fn reflectance(
+    &self,
+    cos_i: f32,
+    thickness: f32,
+    outside: f32,
+    substrate_eta: Vec3,
+    substrate_k: Vec3,
+  ) -> Vec3 {
+    let mut r = Vec3::same(0_f32);
+    for (i, &wavelength) in WAVELENGTHS.iter().enumerate() {
+      r[i] = airy_reflectance(
+        cos_i,
+        wavelength,
+        thickness,
+        outside,
+        self.ior,
+        Complex::new(substrate_eta[i], substrate_k[i]),
+      );
+    }
+    r
+  }
+}
+
+/// Complex index of refraction of a conductor whose normal incidence
+/// reflectance is `reflectivity` and whose reflectance near grazing angles
+/// tends to `edge_tint` (Gulbrandsen, "Artist Friendly Metallic Fresnel",
+/// 2014). Lets an RGB albedo stand in for measured data.
+pub fn conductor_from_reflectivity(
+  reflectivity: Vec3,
+  edge_tint: Vec3,
+) -> (Vec3, Vec3) {
+  let mut eta = Vec3::same(0_f32);
+  let mut k = Vec3::same(0_f32);
+  for i in 0..3 {
+    let r = reflectivity[i].clamp(0_f32, 0.99_f32);
+    let g = edge_tint[i].clamp(0_f32, 1_f32);
+    let sqrt_r = r.sqrt();
+
+    let n = g * (1_f32 - r) / (1_f32 + r)
+      + (1_f32 - g) * (1_f32 + sqrt_r) / (1_f32 - sqrt_r);
+    let k2 = ((n + 1_f32) * (n + 1_f32) * r - (n - 1_f32) * (n - 1_f32))
+      / (1_f32 - r);
+
+    eta[i] = n;
+    k[i] = k2.max(0_f32).sqrt();
+  }
+  (eta, k)
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Complex {
+  re: f32,
+  im: f32,
+}
+
+impl Complex {
+  fn new(re: f32, im: f32) -> Complex {
+    Complex { re, im }
+  }
+
+  fn real(re: f32) -> Complex {
+    Complex::new(re, 0_f32)
+  }
+
+  fn norm_sqr(self) -> f32 {
+    self.re * self.re + self.im * self.im
+  }
+
+  /// Principal square root.
+  fn sqrt(self) -> Complex {
+    let m = self.norm_sqr().sqrt();
+    let re = (0.5_f32 * (m + self.re)).max(0_f32).sqrt();
+    let im = (0.5_f32 * (m - self.re)).max(0_f32).sqrt();
+    Complex::new(re, if self.im < 0_f32 { -im } else { im })
+  }
+
+  /// e^(i theta).
+  fn from_phase(theta: f32) -> Complex {
+    Complex::new(theta.cos(), theta.sin())
+  }
+}
+
+impl Add for Complex {
+  type Output = Complex;
+
+  fn add(self, o: Complex) -> Complex {
+    Complex::new(self.re + o.re, self.im + o.im)
+  }
+}
+
+impl Sub for Complex {
+  type Output = Complex;
+
+  fn sub(self, o: Complex) -> Complex {
+    Complex::new(self.re - o.re, self.im - o.im)
+  }
+}
+
+impl Mul for Complex {
+  type Output = Complex;
+
+  fn mul(self, o: Complex) -> Complex {
+    Complex::new(
+      self.re * o.re - self.im * o.im,
+      self.re * o.im + self.im * o.re,
+    )
+  }
+}
+
+impl Div for Complex {
+  type Output = Complex;
+
+  fn div(self, o: Complex) -> Complex {
+    let d = o.norm_sqr();
+    Complex::new(
+      (self.re * o.re + self.im * o.im) / d,
+      (self.im * o.re - self.re * o.im) / d,
+    )
+  }
+}
+
+/// Fresnel amplitude coefficients (s, p) between media `ni` and `nj`,
+/// given the cosines of the angles in each.
+fn amplitudes(
+  ni: Complex,
+  cos_i: Complex,
+  nj: Complex,
+  cos_j: Complex,
+) -> (Complex, Complex) {
+  let s = (ni * cos_i - nj * cos_j) / (ni * cos_i + nj * cos_j);
+  let p = (nj * cos_i - ni * cos_j) / (nj * cos_i + ni * cos_j);
+  (s, p)
+}
+
+/// Reflectance of a single film summing all the internal reflections
+/// (the Airy formula), averaged over both polarizations.
+fn airy_reflectance(
+  cos_1: f32,
+  wavelength: f32,
+  thickness: f32,
+  n1: f32,
+  n2: f32,
+  n3: Complex,
+) -> f32 {
+  let cos_1 = cos_1.clamp(0_f32, 1_f32);
+  let sin2_1 = Complex::real((1_f32 - cos_1 * cos_1) * n1 * n1);
+
+  //
+  // Snell's law, n sin is the same in every layer.
+  let n1 = Complex::real(n1);
+  let n2 = Complex::real(n2);
+  let cos_1 = Complex::real(cos_1);
+  let cos_2 = (Complex::real(1_f32) - sin2_1 / (n2 * n2)).sqrt();
+  let cos_3 = (Complex::real(1_f32) - sin2_1 / (n3 * n3)).sqrt();
+
+  let (r12_s, r12_p) = amplitudes(n1, cos_1, n2, cos_2);
+  let (r23_s, r23_p) = amplitudes(n2, cos_2, n3, cos_3);
+
+  //
+  // Phase difference of one round trip through the film. An imaginary
+  // cosine means the wave is evanescent in the film and decays instead.
+  let delta =
+    Complex::real(4_f32 * std::f32::consts::PI * thickness / wavelength)
+      * n2
+      * cos_2;
+  let phase = Complex::from_phase(delta.re) * Complex::real((-delta.im).exp());
+
+  let one = Complex::real(1_f32);
+  let r_s = (r12_s + r23_s * phase) / (one + r12_s * r23_s * phase);
+  let r_p = (r12_p + r23_p * phase) / (one + r12_p * r23_p * phase);
+
+  (0.5_f32 * (r_s.norm_sqr() + r_p.norm_sqr())).clamp(0_f32, 1_f32)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+  use crate::fresnel::fresnel_dielectric;
+
+  #[test]
+  fn test_vanishing_film_is_plain_fresnel() {
+    let film = ThinFilm::new(
+      1.33_f32,
+      Arc::new(ConstantTexture::same(0_f32)),
+      0_f32,
+      0_f32,
+    );
+
+    for &cos in [1_f32, 0.7_f32, 0.2_f32].iter() {
+      let r = film.reflectance(
+        cos,
+        0_f32,
+        1_f32,
+        Vec3::same(1.5_f32),
+        Vec3::same(0_f32),
+      );
+      let expected = fresnel_dielectric(cos, 1.5_f32);
+      assert!((r.y - expected).abs() < 1e-4_f32, "{} {}", r.y, expected);
+    }
+  }
+
+  #[test]
+  fn test_quarter_wave_coating_cancels_reflection() {
+    //
+    // A film of index sqrt(1.5) a quarter wavelength thick on glass is a
+    // perfect anti-reflective coating at normal incidence.
+    let ior = 1.5_f32.sqrt();
+    let thickness = WAVELENGTHS[1] / (4_f32 * ior);
+    let film = ThinFilm::new(
+      ior,
+      Arc::new(ConstantTexture::same(0_f32)),
+      thickness,
+      thickness,
+    );
+
+    let r = film.reflectance(
+      1_f32,
+      thickness,
+      1_f32,
+      Vec3::same(1.5_f32),
+      Vec3::same(0_f32),
+    );
+    assert!(r.y < 1e-4_f32, "{:?}", r);
+    assert!(r.x > r.y && r.z > r.y);
+  }
+
+  #[test]
+  fn test_conductor_from_reflectivity() {
+    let albedo = Vec3::new(0.9_f32, 0.6_f32, 0.3_f32);
+    let (eta, k) = conductor_from_reflectivity(albedo, albedo);
+    let film = ThinFilm::new(
+      1.5_f32,
+      Arc::new(ConstantTexture::same(0_f32)),
+      0_f32,
+      0_f32,
+    );
+
+    let r = film.reflectance(1_f32, 0_f32, 1_f32, eta, k);
+    assert!((r - albedo).length() < 1e-3_f32, "{:?}", r);
+  }
+}
@@ -0,0 +1,221 @@
+use super::window::Window;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+/// Order in which tiles are handed out to the workers.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TileOrder {
+  /// Rows of tiles from the top of the image down.
+  Scanline,
+  /// Rings of tiles growing outward from the center of the image.
+  Spiral,
+  /// Along a Hilbert curve, which keeps consecutive tiles adjacent.
+  Hilbert,
+}
+
+impl FromStr for TileOrder {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<TileOrder, String> {
+    match s {
+      "scanline" => Ok(TileOrder::Scanline),
+      "spiral" => Ok(TileOrder::Spiral),
+      "hilbert" => Ok(TileOrder::Hilbert),
+      _ => Err(format!("unknown tile order \"{}\"", s)),
+    }
+  }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Tile {
+  /// Position of the tile in the row-major tile grid, independent of the
+  /// order the tiles are rendered in.
+  pub index: usize,
+  pub bounds: Window<u32>,
+}
+
+/// Maps a cell of a `n` x `n` grid (`n` a power of two) to its distance
+/// along the Hilbert curve.
+fn hilbert_index(n: u32, x: u32, y: u32) -> u64 {
+  let (mut x, mut y) = (x, y);
+  let mut d = 0_u64;
+  let mut s = n / 2;
+
+  while s > 0 {
+    let rx = if x & s != 0 { 1 } else { 0 };
+    let ry = if y & s != 0 { 1 } else { 0 };
+    d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+
+    if ry == 0 {
+      if rx == 1 {
+        x = s - 1 - (x & (s - 1));
+        y = s - 1 - (y & (s - 1));
+      }
+      std::mem::swap(&mut x, &mut y);
+    }
+
+    s /= 2;
+  }
+
+  d
+}
+
+/// Cuts a `width` x `height` image into tiles of at most `tile_size`
+/// pixels on a side, covering every pixel, sorted in the requested order.
+pub fn generate_tiles(
+  width: u32,
+  height: u32,
+  tile_size: u32,
+  order: TileOrder,
+) -> Vec<Tile> {
+  let tiles_x = width.div_ceil(tile_size);
+  let tiles_y = height.div_ceil(tile_size);
+
+  let mut tiles = Vec::with_capacity((tiles_x * tiles_y) as usize);
+  for ty in 0..tiles_y {
+    for tx in 0..tiles_x {
+      tiles.push(Tile {
+        index: (ty * tiles_x + tx) as usize,
+        bounds: Window::new(
+          tx * tile_size,
+          ((tx + 1) * tile_size).min(width),
+          ty * tile_size,
+          ((ty + 1) * tile_size).min(height),
+        ),
+      });
+    }
+  }
+
+  let grid_pos = |t: &Tile| {
+    let i = t.index as u32;
+    (i % tiles_x, i / tiles_x)
+  };
+
+  match order {
+    TileOrder::Scanline => {
+      //
+      // Film row 0 is the bottom of the image.
+      tiles.sort_by_key(|t| {
+        let (tx, ty) = grid_pos(t);
+        (tiles_y - 1 - ty, tx)
+      });
+    }
+    TileOrder::Spiral => {
+      let cx = (tiles_x as f32 - 1_f32) * 0.5_f32;
+      let cy = (tiles_y as f32 - 1_f32) * 0.5_f32;
+
+      let ring_and_angle = |t: &Tile| {
+        let (tx, ty) = grid_pos(t);
+        let dx = tx as f32 - cx;
+        let dy = ty as f32 - cy;
+        (dx.abs().max(dy.abs()), dy.atan2(dx))
+      };
+
+      tiles.sort_by(|a, b| {
+        ring_and_angle(a).partial_cmp(&ring_and_angle(b)).unwrap()
+      });
+    }
+    TileOrder::Hilbert => {
+      let n = tiles_x.max(tiles_y).next_power_of_two();
+      tiles.sort_by_key(|t| {
+        let (tx, ty) = grid_pos(t);
+        hilbert_index(n, tx, ty)
+      });
+    }
+  }
+
+  tiles
+}
+
+/// Work stealing queue. Each worker owns a deque and takes tiles from its
+/// front; once it runs dry it steals from the back of the other workers'
+/// deques, so the tiles still waiting in order are the last to move.
+pub struct WorkQueue {
+  queues: Vec<Mutex<VecDeque<Tile>>>,
+}
+
+impl WorkQueue {
+  /// Deals `tiles` round robin, so every worker starts at the front of the
+  /// requested order.
+  pub fn new(tiles: Vec<Tile>, workers: usize) -> WorkQueue {
+    let workers = workers.max(1);
+    let mut queues = (0..workers).map(|_| VecDeque::new()).collect::<Vec<_>>();
+
+    for (i, tile) in tiles.into_iter().enumerate() {
+      queues[i % workers].push_back(tile);
+    }
+
+    WorkQueue {
+      queues: queues.into_iter().map(Mutex::new).collect(),
+    }
+  }
+
+  pub fn next(&self, worker: usize) -> Option<Tile> {
+    if let Some(tile) = self.queues[worker].lock().unwrap().pop_front() {
+      return Some(tile);
+    }
+
+    let count = self.queues.len();
+    (1..count).find_map(|offset| {
+      self.queues[(worker + offset) % count]
+        .lock()
+        .unwrap()
+        .pop_back()
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn covered_pixels(tiles: &[Tile], width: u32, height: u32) -> Vec<u32> {
+    let mut coverage = vec![0; (width * height) as usize];
+    for t in tiles {
+      for y in t.bounds.ymin..t.bounds.ymax {
+        for x in t.bounds.xmin..t.bounds.xmax {
+          coverage[(y * width + x) as usize] += 1;
+        }
+      }
+    }
+
+    coverage
+  }
+
+  #[test]
+  fn test_tiles_cover_image_exactly_once() {
+    for order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert]
+      .iter()
+    {
+      let tiles = generate_tiles(100, 37, 16, *order);
+      assert_eq!(tiles.len(), 7 * 3);
+      assert!(covered_pixels(&tiles, 100, 37).iter().all(|&c| c == 1));
+    }
+  }
+
+  #[test]
+  fn test_hilbert_order_visits_adjacent_tiles() {
+    let tiles = generate_tiles(64, 64, 8, TileOrder::Hilbert);
+    for pair in tiles.windows(2) {
+      let (a, b) = (pair[0].bounds, pair[1].bounds);
+      let dist = (a.xmin as i32 - b.xmin as i32).abs()
+        + (a.ymin as i32 - b.ymin as i32).abs();
+      assert_eq!(dist, 8);
+    }
+  }
+
+  #[test]
+  fn test_work_queue_hands_out_every_tile_once() {
+    let tiles = generate_tiles(64, 64, 8, TileOrder::Spiral);
+    let queue = WorkQueue::new(tiles, 3);
+
+    let mut seen = [false; 64];
+    while let Some(tile) = queue.next(1) {
+      assert!(!seen[tile.index]);
+      seen[tile.index] = true;
+    }
+
+    assert!(seen.iter().all(|&s| s));
+  }
+}
@@ -0,0 +1,201 @@
+use super::ray_differential::Footprint;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+/// Projects a 2D texture onto a surface along the X, Y and Z axes and
+/// blends the three by how squarely the surface faces each one, so it
+/// needs no (u, v) of its own. Works in world space from the hit position
+/// and shading normal; looked up without a hit, every projection gets the
+/// same weight.
+pub struct Triplanar {
+  inner: Arc<dyn Texture>,
+  /// Repeats of the texture per world unit.
+  scale: f32,
+  /// How quickly a projection fades out as the surface turns away from
+  /// it. 1 blends softly, higher values give narrow seams.
+  sharpness: f32,
+}
+
+impl Triplanar {
+  pub fn new(inner: Arc<dyn Texture>, scale: f32) -> Triplanar {
+    Triplanar {
+      inner,
+      scale,
+      sharpness: 4_f32,
+    }
+  }
+
+  pub fn with_sharpness(mut self, sharpness: f32) -> Triplanar {
+    self.sharpness = sharpness.max(1e-3_f32);
+    self
+  }
+
+  /// Blend of the three projections at `at`, weighted by its normal.
+  fn blend(&self, at: &TexturePoint) -> Vec3 {
+    let weights = self.weights(at.normal);
+
+    let mut sum = Vec3::same(0_f32);
+    for axis in 0..3 {
+      if weights[axis] < 1e-4_f32 {
+        continue;
+      }
+
+      //
+      // Project onto the other two axes, mirrored on the negative side so
+      // the texture reads the right way round from every direction. The
+      // footprint is projected and mirrored the same way.
+      let flip = if at.normal[axis] < 0_f32 {
+        -self.scale
+      } else {
+        self.scale
+      };
+      let (a, b) = projected(at.p, axis);
+      let footprint = at.footprint.map(|f| {
+        let (dudx, dvdx) = projected(f.dpdx, axis);
+        let (dudy, dvdy) = projected(f.dpdy, axis);
+        Footprint {
+          dudx: flip * dudx,
+          dvdx: self.scale * dvdx,
+          dudy: flip * dudy,
+          dvdy: self.scale * dvdy,
+          ..f
+        }
+      });
+      let projection = TexturePoint {
+        u: flip * a,
+        v: self.scale * b,
+        footprint,
+        ..*at
+      };
+      sum += weights[axis] * self.inner.lookup(&projection);
+    }
+    sum
+  }
+
+  fn weights(&self, n: Vec3) -> Vec3 {
+    let w = Vec3::new(
+      n.x.abs().powf(self.sharpness),
+      n.y.abs().powf(self.sharpness),
+      n.z.abs().powf(self.sharpness),
+    );
+    w / (w.x + w.y + w.z)
+  }
+}
+
+/// The components of `p` across `axis`, as (u, v) of the projection
+/// along it. The second axis is up where possible.
+fn projected(p: Vec3, axis: usize) -> (f32, f32) {
+  match axis {
+    0 => (p.z, p.y),
+    1 => (p.x, p.z),
+    _ => (p.x, p.y),
+  }
+}
+
+impl Texture for Triplanar {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.blend(at)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+  use crate::hitable::HitRecord;
+  use crate::lambertian::Lambertian;
+  use crate::ray::Ray;
+  use crate::texture_math::ScaleTexture;
+
+  /// Returns its (u, v) as a colour.
+  struct Coordinates;
+
+  impl Texture for Coordinates {
+    fn lookup(&self, at: &TexturePoint) -> Vec3 {
+      Vec3::new(at.u, at.v, 0_f32)
+    }
+  }
+
+  /// Returns how fast u changes across the footprint.
+  struct UDerivatives;
+
+  impl Texture for UDerivatives {
+    fn lookup(&self, at: &TexturePoint) -> Vec3 {
+      at.footprint
+        .map_or(Vec3::same(0_f32), |f| Vec3::new(f.dudx, f.dudy, 0_f32))
+    }
+  }
+
+  fn facing(n: Vec3, dpdx: Vec3, dpdy: Vec3) -> TexturePoint {
+    TexturePoint {
+      u: 0_f32,
+      v: 0_f32,
+      p: Vec3::same(0_f32),
+      normal: n,
+      footprint: Some(Footprint {
+        dpdx,
+        dpdy,
+        dudx: 0_f32,
+        dvdx: 0_f32,
+        dudy: 0_f32,
+        dvdy: 0_f32,
+      }),
+    }
+  }
+
+  #[test]
+  fn test_projection_follows_the_normal() {
+    let t = Triplanar::new(Arc::new(Coordinates), 2_f32);
+    let mtl =
+      Arc::new(Lambertian::new(Arc::new(ConstantTexture::same(0.5_f32))));
+    let p = Vec3::new(0.1_f32, 0.2_f32, 0.3_f32);
+    let r = Ray::new(Vec3::same(0_f32), p, 0_f32);
+
+    //
+    // Facing straight up only the projection along Y counts.
+    let up = Vec3::new(0_f32, 1_f32, 0_f32);
+    let h = HitRecord::new(1_f32, p, up, mtl.clone(), 0_f32, 0_f32);
+    let c = t.value_at(&r, &h);
+    assert!((c - Vec3::new(0.2_f32, 0.6_f32, 0_f32)).length() < 1e-5_f32);
+
+    //
+    // Halfway between X and Z both count equally, and the weights of the
+    // projections still add up to one.
+    let n = Vec3::new(-1_f32, 0_f32, 1_f32) / 2_f32.sqrt();
+    let h = HitRecord::new(1_f32, p, n, mtl, 0_f32, 0_f32);
+    let c = t.value_at(&r, &h);
+    let expected = 0.5_f32 * Vec3::new(-0.6_f32, 0.4_f32, 0_f32)
+      + 0.5_f32 * Vec3::new(0.2_f32, 0.4_f32, 0_f32);
+    assert!((c - expected).length() < 1e-5_f32);
+  }
+
+  #[test]
+  fn test_projection_through_other_textures() {
+    let t = Arc::new(Triplanar::new(Arc::new(Coordinates), 2_f32));
+    let scaled = ScaleTexture::new(t.clone(), 1_f32);
+    let mtl =
+      Arc::new(Lambertian::new(Arc::new(ConstantTexture::same(0.5_f32))));
+    let p = Vec3::new(0.1_f32, 0.2_f32, 0.3_f32);
+    let r = Ray::new(Vec3::same(0_f32), p, 0_f32);
+    let up = Vec3::new(0_f32, 1_f32, 0_f32);
+    let h = HitRecord::new(1_f32, p, up, mtl, 0_f32, 0_f32);
+
+    assert_eq!(scaled.value_at(&r, &h), t.value_at(&r, &h));
+    assert!((scaled.scalar_at(&r, &h) - 0.8_f32 / 3_f32).abs() < 1e-5_f32);
+  }
+
+  #[test]
+  fn test_footprint_is_mirrored_with_the_projection() {
+    let t = Triplanar::new(Arc::new(UDerivatives), 1_f32);
+    let dpdx = Vec3::new(0_f32, 0_f32, 0.1_f32);
+    let dpdy = Vec3::new(0_f32, 0_f32, 0.2_f32);
+
+    //
+    // Along X, u runs with z and flips on the -X side.
+    let front = t.lookup(&facing(Vec3::new(1_f32, 0_f32, 0_f32), dpdx, dpdy));
+    assert_eq!(front, Vec3::new(0.1_f32, 0.2_f32, 0_f32));
+    let back = t.lookup(&facing(Vec3::new(-1_f32, 0_f32, 0_f32), dpdx, dpdy));
+    assert_eq!(back, Vec3::new(-0.1_f32, -0.2_f32, 0_f32));
+  }
+}
@@ -0,0 +1,225 @@
+use super::texture::{Texture, TexturePoint};
+use super::vec3::Vec3;
+use std::sync::Arc;
+
+/// Index of the tile that `x` in [0, 1] falls in, when [0, 1] is split
+/// into `tiles`, and how far across that tile it is.
+fn tile(x: f32, tiles: f32) -> (i32, f32) {
+  let x = x * tiles;
+  let i = x.floor();
+  (i as i32, x - i)
+}
+
+/// A checker board laid out in texture space, so the squares follow the
+/// surface parametrization instead of cutting through it.
+pub struct UvCheckerTexture {
+  odd: Arc<dyn Texture>,
+  even: Arc<dyn Texture>,
+  /// Number of squares across u and v.
+  tiles_u: f32,
+  tiles_v: f32,
+}
+
+impl UvCheckerTexture {
+  pub fn new(
+    odd: Arc<dyn Texture>,
+    even: Arc<dyn Texture>,
+    tiles_u: u32,
+    tiles_v: u32,
+  ) -> UvCheckerTexture {
+    UvCheckerTexture {
+      odd,
+      even,
+      tiles_u: tiles_u as f32,
+      tiles_v: tiles_v as f32,
+    }
+  }
+
+  /// The texture of the square (u, v) falls in.
+  fn pick(&self, u: f32, v: f32) -> &dyn Texture {
+    let (i, _) = tile(u, self.tiles_u);
+    let (j, _) = tile(v, self.tiles_v);
+    if (i + j) % 2 == 0 {
+      self.even.as_ref()
+    } else {
+      self.odd.as_ref()
+    }
+  }
+}
+
+impl Texture for UvCheckerTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.pick(at.u, at.v).lookup(at)
+  }
+}
+
+/// Lines along the edges of a grid of tiles in texture space, like tiles
+/// and grout or graph paper.
+pub struct GridTexture {
+  line: Arc<dyn Texture>,
+  fill: Arc<dyn Texture>,
+  tiles_u: f32,
+  tiles_v: f32,
+  /// Width of the lines as a fraction of a tile.
+  line_width: f32,
+}
+
+impl GridTexture {
+  pub fn new(
+    line: Arc<dyn Texture>,
+    fill: Arc<dyn Texture>,
+    tiles_u: u32,
+    tiles_v: u32,
+    line_width: f32,
+  ) -> GridTexture {
+    GridTexture {
+      line,
+      fill,
+      tiles_u: tiles_u as f32,
+      tiles_v: tiles_v as f32,
+      line_width: line_width.clamp(0_f32, 1_f32),
+    }
+  }
+
+  /// The line texture if (u, v) is on a line, else the fill.
+  fn pick(&self, u: f32, v: f32) -> &dyn Texture {
+    //
+    // Lines are centred on the tile edges, half on either side.
+    let half = 0.5_f32 * self.line_width;
+    let on_line = |f: f32| f < half || f > 1_f32 - half;
+
+    let (_, fu) = tile(u, self.tiles_u);
+    let (_, fv) = tile(v, self.tiles_v);
+    if on_line(fu) || on_line(fv) {
+      self.line.as_ref()
+    } else {
+      self.fill.as_ref()
+    }
+  }
+}
+
+impl Texture for GridTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.pick(at.u, at.v).lookup(at)
+  }
+}
+
+/// A checker of cubes filling space, for objects without a useful
+/// parametrization. Surfaces lying on a cube face, such as a floor at
+/// `y = 0`, flicker between the two sides.
+pub struct SolidCheckerTexture {
+  odd: Arc<dyn Texture>,
+  even: Arc<dyn Texture>,
+  /// Cubes per world unit.
+  scale: f32,
+}
+
+impl SolidCheckerTexture {
+  pub fn new(
+    odd: Arc<dyn Texture>,
+    even: Arc<dyn Texture>,
+    scale: f32,
+  ) -> SolidCheckerTexture {
+    SolidCheckerTexture { odd, even, scale }
+  }
+
+  /// The texture of the cube `p` falls in.
+  fn pick(&self, p: Vec3) -> &dyn Texture {
+    let q = self.scale * p;
+    let sum = q.x.floor() as i32 + q.y.floor() as i32 + q.z.floor() as i32;
+    if sum % 2 == 0 {
+      self.even.as_ref()
+    } else {
+      self.odd.as_ref()
+    }
+  }
+}
+
+impl Texture for SolidCheckerTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.pick(at.p).lookup(at)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::constant_texture::ConstantTexture;
+  use crate::ray_differential::Footprint;
+
+  fn black_and_white() -> (Arc<dyn Texture>, Arc<dyn Texture>) {
+    (
+      Arc::new(ConstantTexture::same(0_f32)),
+      Arc::new(ConstantTexture::same(1_f32)),
+    )
+  }
+
+  #[test]
+  fn test_uv_checker_alternates() {
+    let (black, white) = black_and_white();
+    let checker = UvCheckerTexture::new(black, white, 4, 2);
+    let p = Vec3::same(0_f32);
+
+    assert_eq!(checker.value(0.1_f32, 0.1_f32, p).x, 1_f32);
+    assert_eq!(checker.value(0.3_f32, 0.1_f32, p).x, 0_f32);
+    assert_eq!(checker.value(0.3_f32, 0.6_f32, p).x, 1_f32);
+  }
+
+  #[test]
+  fn test_solid_checker_handles_negative_cells() {
+    let (black, white) = black_and_white();
+    let checker = SolidCheckerTexture::new(black, white, 2_f32);
+    let p = Vec3::new(0.25_f32, 0.25_f32, 0.25_f32);
+
+    assert_eq!(checker.value(0_f32, 0_f32, p).x, 1_f32);
+    assert_eq!(checker.value(0_f32, 0_f32, -p).x, 0_f32);
+    assert_eq!(checker.value(0_f32, 0_f32, -3_f32 * p).x, 1_f32);
+  }
+
+  /// White unless it is looked up over a footprint, which comes out grey.
+  struct Blurred;
+
+  impl Texture for Blurred {
+    fn lookup(&self, at: &TexturePoint) -> Vec3 {
+      match at.footprint {
+        Some(_) => Vec3::same(0.5_f32),
+        None => Vec3::same(1_f32),
+      }
+    }
+  }
+
+  #[test]
+  fn test_checkers_forward_the_footprint() {
+    let blurred: Arc<dyn Texture> = Arc::new(Blurred);
+    let at = TexturePoint {
+      u: 0.3_f32,
+      v: 0.3_f32,
+      p: Vec3::same(0.25_f32),
+      normal: Vec3::new(0_f32, 1_f32, 0_f32),
+      footprint: Some(Footprint {
+        dpdx: Vec3::same(0_f32),
+        dpdy: Vec3::same(0_f32),
+        dudx: 0.1_f32,
+        dvdx: 0_f32,
+        dudy: 0_f32,
+        dvdy: 0.1_f32,
+      }),
+    };
+    let grey = Vec3::same(0.5_f32);
+
+    let textures: [Box<dyn Texture>; 3] = [
+      Box::new(UvCheckerTexture::new(blurred.clone(), blurred.clone(), 4, 4)),
+      Box::new(GridTexture::new(
+        blurred.clone(),
+        blurred.clone(),
+        4,
+        4,
+        0.1_f32,
+      )),
+      Box::new(SolidCheckerTexture::new(blurred.clone(), blurred, 2_f32)),
+    ];
+    for t in textures.iter() {
+      assert_eq!(t.lookup(&at), grey);
+    }
+  }
+}
@@ -238,6 +238,18 @@ pub fn random_in_unit_disk() -> Vec3 {
   }
 }
 
+/// Cosine weighted direction on the hemisphere around +z, with density
+/// `z / pi`.
+pub fn random_cosine_direction() -> Vec3 {
+  let mut rng = thread_rng();
+  let r1: f32 = rng.gen();
+  let r2: f32 = rng.gen();
+  let phi = 2f32 * std::f32::consts::PI * r1;
+  let r = r2.sqrt();
+
+  Vec3::new(r * phi.cos(), r * phi.sin(), (1f32 - r2).sqrt())
+}
+
 pub fn reflect(v: Vec3, n: Vec3) -> Vec3 {
   v - 2f32 * dot_product(v, n) * n
 }
@@ -0,0 +1,144 @@
+use super::perlin::PerlinNoise;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::{dot_product, unit_vector, Vec3};
+
+/// Rings the turbulence spans, so changing the spacing scales the whole
+/// pattern.
+const RINGS_PER_WOBBLE: f32 = 10_f32;
+
+/// Growth rings around an axis, as if the object were carved from a log.
+/// Turbulence wobbles the rings and fine streaks along the axis add grain.
+pub struct WoodTexture {
+  noise: PerlinNoise,
+  /// A point on the centre line of the log.
+  pub center: Vec3,
+  /// Direction the trunk grew in.
+  pub axis: Vec3,
+  /// Distance between rings in world units.
+  pub ring_spacing: f32,
+  /// How far the rings wobble, as a fraction of the ring spacing.
+  pub distortion: f32,
+  /// Strength of the streaks along the axis, from 0 to 1.
+  pub grain: f32,
+  /// Colours of early and late wood, across each ring.
+  pub light: Vec3,
+  pub dark: Vec3,
+}
+
+impl WoodTexture {
+  pub fn new(seed: u64, light: Vec3, dark: Vec3) -> WoodTexture {
+    WoodTexture {
+      noise: PerlinNoise::with_seed(seed),
+      center: Vec3::same(0_f32),
+      axis: Vec3::new(0_f32, 1_f32, 0_f32),
+      ring_spacing: 0.1_f32,
+      distortion: 0.6_f32,
+      grain: 0.3_f32,
+      light,
+      dark,
+    }
+  }
+
+  /// Pale wood with fine, even rings.
+  pub fn oak(seed: u64) -> WoodTexture {
+    WoodTexture::new(
+      seed,
+      Vec3::new(0.75_f32, 0.55_f32, 0.3_f32),
+      Vec3::new(0.45_f32, 0.28_f32, 0.12_f32),
+    )
+  }
+
+  /// Dark reddish wood with wide rings.
+  pub fn walnut(seed: u64) -> WoodTexture {
+    WoodTexture::new(
+      seed,
+      Vec3::new(0.4_f32, 0.22_f32, 0.12_f32),
+      Vec3::new(0.15_f32, 0.07_f32, 0.04_f32),
+    )
+    .with_ring_spacing(0.18_f32)
+  }
+
+  pub fn with_axis(mut self, center: Vec3, axis: Vec3) -> WoodTexture {
+    self.center = center;
+    self.axis = unit_vector(axis);
+    self
+  }
+
+  pub fn with_ring_spacing(mut self, ring_spacing: f32) -> WoodTexture {
+    self.ring_spacing = ring_spacing;
+    self
+  }
+
+  pub fn with_distortion(mut self, distortion: f32) -> WoodTexture {
+    self.distortion = distortion;
+    self
+  }
+
+  pub fn with_grain(mut self, grain: f32) -> WoodTexture {
+    self.grain = grain.clamp(0_f32, 1_f32);
+    self
+  }
+}
+
+impl Texture for WoodTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    let d = at.p - self.center;
+    let along = dot_product(d, self.axis);
+    let radius = (d - along * self.axis).length();
+
+    //
+    // Rings are measured in ring widths so the wobble keeps its look
+    // whatever the spacing.
+    let wobble = at.p / (RINGS_PER_WOBBLE * self.ring_spacing);
+    let rings = radius / self.ring_spacing
+      + self.distortion * self.noise.turbulence(wobble, 5);
+    let ring = rings - rings.floor();
+
+    //
+    // Late wood is a thin dark band at the end of each year.
+    let t = ring * ring * ring;
+
+    //
+    // Fibres are stretched along the axis: high frequency across it and
+    // low frequency along it.
+    let across = (d - along * self.axis) * (40_f32 / self.ring_spacing);
+    let fibres = self.noise.noise(across + self.axis * along * 2_f32);
+    let streak = 1_f32 - self.grain * (0.5_f32 + 0.5_f32 * fibres);
+
+    streak * ((1_f32 - t) * self.light + t * self.dark)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::noise_texture::{check_preset, sample_points};
+
+  #[test]
+  fn test_wood_presets() {
+    //
+    // Grain only darkens.
+    let oak = WoodTexture::oak(0);
+    check_preset(WoodTexture::oak, Vec3::same(0_f32), oak.light);
+    let walnut = WoodTexture::walnut(0);
+    check_preset(WoodTexture::walnut, Vec3::same(0_f32), walnut.light);
+  }
+
+  #[test]
+  fn test_rings_follow_their_spacing() {
+    //
+    // Doubling the spacing doubles the size of the whole pattern, wobble
+    // included. The streaks don't scale along the axis, so they're off.
+    let wood = |spacing: f32| {
+      WoodTexture::oak(3)
+        .with_ring_spacing(spacing)
+        .with_grain(0_f32)
+    };
+    let (fine, coarse) = (wood(0.1_f32), wood(0.2_f32));
+    for p in sample_points() {
+      let a = fine.value(0_f32, 0_f32, p);
+      let b = coarse.value(0_f32, 0_f32, 2_f32 * p);
+      assert!((a - b).length() < 1e-3_f32, "{:?} {:?}", a, b);
+    }
+  }
+}
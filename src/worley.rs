@@ -0,0 +1,248 @@
+use super::color_ramp::ColorRamp;
+use super::texture::{Texture, TexturePoint};
+use super::vec3::Vec3;
+
+/// How the distance from a point to a feature point is measured, which
+/// sets the shape of the cells.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DistanceMetric {
+  /// Round cells with straight borders.
+  Euclidean,
+  /// Diamond shaped cells, for tiles and crystals.
+  Manhattan,
+  /// Square cells, for blocky stone work.
+  Chebyshev,
+}
+
+impl DistanceMetric {
+  fn distance(self, d: Vec3) -> f32 {
+    match self {
+      DistanceMetric::Euclidean => d.length(),
+      DistanceMetric::Manhattan => d.x.abs() + d.y.abs() + d.z.abs(),
+      DistanceMetric::Chebyshev => d.x.abs().max(d.y.abs()).max(d.z.abs()),
+    }
+  }
+}
+
+/// Which distance the texture shows.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WorleyFeature {
+  /// Distance to the closest feature point: dark cell centres.
+  F1,
+  /// Distance to the second closest feature point.
+  F2,
+  /// Zero along the cell borders, for cracks and mortar lines.
+  F2MinusF1,
+}
+
+/// Cellular noise (Worley, "A Cellular Texture Basis Function", 1996).
+/// Space is cut into unit cells, each holding one randomly placed feature
+/// point, and the noise is the distance to the nearest ones.
+pub struct WorleyNoise {
+  seed: u64,
+  pub metric: DistanceMetric,
+  /// Cells per world unit.
+  pub frequency: f32,
+  /// How far feature points may wander from the cell centre, from 0 for
+  /// a regular grid to 1 for fully random cells.
+  pub jitter: f32,
+}
+
+impl WorleyNoise {
+  pub fn new(seed: u64) -> WorleyNoise {
+    WorleyNoise {
+      seed,
+      metric: DistanceMetric::Euclidean,
+      frequency: 1_f32,
+      jitter: 1_f32,
+    }
+  }
+
+  pub fn with_metric(mut self, metric: DistanceMetric) -> WorleyNoise {
+    self.metric = metric;
+    self
+  }
+
+  pub fn with_frequency(mut self, frequency: f32) -> WorleyNoise {
+    self.frequency = frequency;
+    self
+  }
+
+  pub fn with_jitter(mut self, jitter: f32) -> WorleyNoise {
+    self.jitter = jitter.clamp(0_f32, 1_f32);
+    self
+  }
+
+  /// Distances to the closest and second closest feature points, in
+  /// cells.
+  pub fn distances(&self, p: Vec3) -> (f32, f32) {
+    let p = self.frequency * p;
+    let cell = Vec3::new(p.x.floor(), p.y.floor(), p.z.floor());
+
+    //
+    // With full jitter the second closest point can be two or more cells
+    // away, so cells are searched in shells around the one holding `p`.
+    // A feature point stays in its cell, so a cell whose nearest face is
+    // no closer than F2 is skipped. Every cell of shell n is at least
+    // n - 1 cells away along some axis, so the search ends once that
+    // reaches F2.
+    let gap = |lo: f32, x: f32| (lo - x).max(x - lo - 1_f32).max(0_f32);
+    let mut f1 = f32::MAX;
+    let mut f2 = f32::MAX;
+    let mut shell = 0_i32;
+    while ((shell - 1) as f32) < f2 {
+      for i in -shell..=shell {
+        for j in -shell..=shell {
+          for k in -shell..=shell {
+            if i.abs().max(j.abs()).max(k.abs()) != shell {
+              continue;
+            }
+
+            let c = cell + Vec3::new(i as f32, j as f32, k as f32);
+            let nearest =
+              Vec3::new(gap(c.x, p.x), gap(c.y, p.y), gap(c.z, p.z));
+            if self.metric.distance(nearest) >= f2 {
+              continue;
+            }
+
+            let d = self.metric.distance(self.feature_point(c) - p);
+            if d < f1 {
+              f2 = f1;
+              f1 = d;
+            } else if d < f2 {
+              f2 = d;
+            }
+          }
+        }
+      }
+
+      shell += 1;
+    }
+
+    (f1, f2)
+  }
+
+  pub fn evaluate(&self, p: Vec3, feature: WorleyFeature) -> f32 {
+    let (f1, f2) = self.distances(p);
+    match feature {
+      WorleyFeature::F1 => f1,
+      WorleyFeature::F2 => f2,
+      WorleyFeature::F2MinusF1 => f2 - f1,
+    }
+  }
+
+  /// The feature point of the cell whose lower corner is `c`.
+  fn feature_point(&self, c: Vec3) -> Vec3 {
+    let mut h = self.seed ^ 0x9e37_79b9_7f4a_7c15_u64;
+    for &x in [c.x, c.y, c.z].iter() {
+      h ^= (x as i32) as u32 as u64;
+      h = h.wrapping_mul(0xbf58_476d_1ce4_e5b9_u64);
+      h ^= h >> 31;
+    }
+
+    let offset = |bits: u64| {
+      let u = (bits & 0xff_ffff) as f32 / (1_u64 << 24) as f32;
+      0.5_f32 + self.jitter * (u - 0.5_f32)
+    };
+    c + Vec3::new(offset(h), offset(h >> 21), offset(h >> 42))
+  }
+}
+
+/// A Worley feature through a colour ramp, for cells, cracked earth and
+/// stone tiles.
+pub struct WorleyTexture {
+  noise: WorleyNoise,
+  feature: WorleyFeature,
+  ramp: ColorRamp,
+}
+
+impl WorleyTexture {
+  pub fn new(
+    noise: WorleyNoise,
+    feature: WorleyFeature,
+    ramp: ColorRamp,
+  ) -> WorleyTexture {
+    WorleyTexture {
+      noise,
+      feature,
+      ramp,
+    }
+  }
+}
+
+impl Texture for WorleyTexture {
+  fn lookup(&self, at: &TexturePoint) -> Vec3 {
+    self.ramp.evaluate(self.noise.evaluate(at.p, self.feature))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_regular_grid_distances() {
+    //
+    // Without jitter the feature points sit at the cell centres.
+    let noise = WorleyNoise::new(7).with_jitter(0_f32);
+    let p = Vec3::new(0.5_f32, 0.5_f32, 0.5_f32);
+    assert_eq!(noise.distances(p), (0_f32, 1_f32));
+
+    let p = Vec3::new(1_f32, 0.5_f32, 0.5_f32);
+    let (f1, f2) = noise.distances(p);
+    assert!((f1 - 0.5_f32).abs() < 1e-6_f32);
+    assert!(noise.evaluate(p, WorleyFeature::F2MinusF1).abs() < 1e-6_f32);
+    assert!((f2 - f1).abs() < 1e-6_f32);
+  }
+
+  #[test]
+  fn test_features_are_ordered() {
+    for &metric in [
+      DistanceMetric::Euclidean,
+      DistanceMetric::Manhattan,
+      DistanceMetric::Chebyshev,
+    ]
+    .iter()
+    {
+      let noise = WorleyNoise::new(3).with_metric(metric);
+      for i in 0..100 {
+        let p = Vec3::new(i as f32 * 0.37_f32, -(i as f32) * 0.11_f32, 2_f32);
+        let (f1, f2) = noise.distances(p);
+        assert!(0_f32 <= f1 && f1 <= f2, "{:?} {} {}", metric, f1, f2);
+      }
+    }
+  }
+
+  #[test]
+  fn test_distances_match_a_brute_force_search() {
+    for &metric in [
+      DistanceMetric::Euclidean,
+      DistanceMetric::Manhattan,
+      DistanceMetric::Chebyshev,
+    ]
+    .iter()
+    {
+      let noise = WorleyNoise::new(11).with_metric(metric);
+      for i in 0..2000 {
+        let t = i as f32 * 0.0731_f32;
+        let p = Vec3::new(t.sin() * 9_f32, (1.3_f32 * t).cos() * 9_f32, t);
+
+        //
+        // Every feature point within three cells.
+        let cell = Vec3::new(p.x.floor(), p.y.floor(), p.z.floor());
+        let mut d = Vec::new();
+        for i in -3..=3 {
+          for j in -3..=3 {
+            for k in -3..=3 {
+              let c = cell + Vec3::new(i as f32, j as f32, k as f32);
+              d.push(metric.distance(noise.feature_point(c) - p));
+            }
+          }
+        }
+        d.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(noise.distances(p), (d[0], d[1]), "{:?} {:?}", metric, p);
+      }
+    }
+  }
+}
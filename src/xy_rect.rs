@@ -11,7 +11,7 @@ pub struct XYRect {
   pub y0: f32,
   pub y1: f32,
   pub k: f32,
-  pub mtl: Arc<Material>,
+  pub mtl: Arc<dyn Material>,
 }
 
 impl XYRect {
@@ -21,7 +21,7 @@ impl XYRect {
     y0: f32,
     y1: f32,
     k: f32,
-    mtl: Arc<Material>,
+    mtl: Arc<dyn Material>,
   ) -> XYRect {
     XYRect {
       x0,
@@ -48,17 +48,26 @@ impl Hitable for XYRect {
       return None;
     }
 
-    Some(HitRecord::new(
-      t,
-      r.point_at_param(t),
-      Vec3::new(0_f32, 0_f32, 1_f32),
-      self.mtl.clone(),
-      (x - self.x0) / (self.x1 - self.x0),
-      (y - self.y0) / (self.y1 - self.y0),
-    ))
+    Some(
+      HitRecord::new(
+        t,
+        r.point_at_param(t),
+        Vec3::new(0_f32, 0_f32, 1_f32),
+        self.mtl.clone(),
+        (x - self.x0) / (self.x1 - self.x0),
+        (y - self.y0) / (self.y1 - self.y0),
+      )
+      .with_tangent(Vec3::new(1_f32, 0_f32, 0_f32))
+      .with_derivatives(
+        Vec3::new(self.x1 - self.x0, 0_f32, 0_f32),
+        Vec3::new(0_f32, self.y1 - self.y0, 0_f32),
+        Vec3::same(0_f32),
+        Vec3::same(0_f32),
+      ),
+    )
   }
 
-  fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+  fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
     Some(Aabb::new(
       Vec3::new(self.x0, self.y0, self.k - 0.0001_f32),
       Vec3::new(self.x1, self.y1, self.k + 0.0001_f32),
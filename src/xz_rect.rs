@@ -11,7 +11,7 @@ pub struct XZRect {
   pub x1: f32,
   pub z1: f32,
   pub k: f32,
-  pub mtl: Arc<Material>,
+  pub mtl: Arc<dyn Material>,
 }
 
 impl XZRect {
@@ -21,7 +21,7 @@ impl XZRect {
     z0: f32,
     z1: f32,
     k: f32,
-    mtl: Arc<Material>,
+    mtl: Arc<dyn Material>,
   ) -> XZRect {
     XZRect {
       x0,
@@ -32,6 +32,10 @@ impl XZRect {
       mtl,
     }
   }
+
+  pub fn area(&self) -> f32 {
+    (self.x1 - self.x0) * (self.z1 - self.z0)
+  }
 }
 
 impl Hitable for XZRect {
@@ -48,14 +52,23 @@ impl Hitable for XZRect {
       return None;
     }
 
-    Some(HitRecord::new(
-      t,
-      r.point_at_param(t),
-      Vec3::new(0_f32, 1_f32, 0_f32),
-      self.mtl.clone(),
-      (x - self.x0) / (self.x1 - self.x0),
-      (z - self.z0) / (self.z1 - self.z0),
-    ))
+    Some(
+      HitRecord::new(
+        t,
+        r.point_at_param(t),
+        Vec3::new(0_f32, 1_f32, 0_f32),
+        self.mtl.clone(),
+        (x - self.x0) / (self.x1 - self.x0),
+        (z - self.z0) / (self.z1 - self.z0),
+      )
+      .with_tangent(Vec3::new(1_f32, 0_f32, 0_f32))
+      .with_derivatives(
+        Vec3::new(self.x1 - self.x0, 0_f32, 0_f32),
+        Vec3::new(0_f32, 0_f32, self.z1 - self.z0),
+        Vec3::same(0_f32),
+        Vec3::same(0_f32),
+      ),
+    )
   }
 
   fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
@@ -11,7 +11,7 @@ pub struct YZRect {
   pub z0: f32,
   pub z1: f32,
   pub k: f32,
-  pub mtl: Arc<Material>,
+  pub mtl: Arc<dyn Material>,
 }
 
 impl YZRect {
@@ -21,7 +21,7 @@ impl YZRect {
     z0: f32,
     z1: f32,
     k: f32,
-    mtl: Arc<Material>,
+    mtl: Arc<dyn Material>,
   ) -> YZRect {
     YZRect {
       y0,
@@ -48,14 +48,23 @@ impl Hitable for YZRect {
       return None;
     }
 
-    Some(HitRecord::new(
-      t,
-      r.point_at_param(t),
-      Vec3::new(1_f32, 0_f32, 0_f32),
-      self.mtl.clone(),
-      (y - self.y0) / (self.y1 - self.y0),
-      (z - self.z0) / (self.z1 - self.z0),
-    ))
+    Some(
+      HitRecord::new(
+        t,
+        r.point_at_param(t),
+        Vec3::new(1_f32, 0_f32, 0_f32),
+        self.mtl.clone(),
+        (y - self.y0) / (self.y1 - self.y0),
+        (z - self.z0) / (self.z1 - self.z0),
+      )
+      .with_tangent(Vec3::new(0_f32, 1_f32, 0_f32))
+      .with_derivatives(
+        Vec3::new(0_f32, self.y1 - self.y0, 0_f32),
+        Vec3::new(0_f32, 0_f32, self.z1 - self.z0),
+        Vec3::same(0_f32),
+        Vec3::same(0_f32),
+      ),
+    )
   }
 
   fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {